@@ -1,2 +1,8 @@
 pub mod common;
+pub mod delegating;
+pub mod metered;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub mod reconnect;
+pub mod recording;
 pub mod rest;
\ No newline at end of file
@@ -1,17 +1,132 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::error::Error;
+use crate::framework::logger::Logger;
 use crate::Result;
 use crate::schema::field::Field;
-use crate::schema::entity::Entity;
-use crate::schema::notification::{Notification, Config, Token};
+use crate::schema::entity::{Entity, EntityMetadata, EntityPage, EntitySchema};
+use crate::schema::notification::{Notification, Config, Lease, Token};
+use crate::schema::server::ServerInfo;
 
 pub trait ClientTrait {
+    /// Attempts to connect. A client may skip the attempt, for instance
+    /// while reconnects are being rate limited, and still return `Ok(())`;
+    /// an `Ok` only means nothing failed, so check `connected` afterwards.
     fn connect(&mut self) -> Result<()>;
     fn connected(&self) -> bool;
     fn disconnect(&mut self) -> bool;
     fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>>;
     fn get_entity(&mut self, entity_id: &str) -> Result<Entity>;
     fn get_notifications(&mut self) -> Result<Vec<Notification>>;
+    #[allow(clippy::ptr_arg)]
     fn read(&mut self, requests: &Vec<Field>) -> Result<()>;
     fn register_notification(&mut self, config: &Config) -> Result<Token>;
     fn unregister_notification(&mut self, token: &Token) -> Result<()>;
+    #[allow(clippy::ptr_arg)]
     fn write(&mut self, requests: &Vec<Field>) -> Result<()>;
-}
\ No newline at end of file
+
+    /// Gives the client a logger for warnings about what it receives.
+    /// Clients that don't log ignore it.
+    fn set_logger(&mut self, _logger: Logger) {}
+
+    /// Bounds how long each request may take. Clients without their own
+    /// timeout handling ignore it.
+    fn set_timeout(&mut self, _timeout: Option<Duration>) {}
+
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn write_at(&mut self, _requests: &Vec<Field>, _write_time: DateTime<Utc>) -> Result<()> {
+        Err(Error::from_unsupported("Writing with an explicit write time is not supported"))
+    }
+
+    fn server_info(&mut self) -> Result<ServerInfo> {
+        Err(Error::from_unsupported("Server info is not supported"))
+    }
+
+    fn get_entity_metadata(&mut self, _entity_id: &str) -> Result<EntityMetadata> {
+        Err(Error::from_unsupported("Entity metadata is not supported"))
+    }
+
+    /// Fetches up to `page_size` entities of `entity_type`, starting at
+    /// `cursor` or at the first entity when it is `None`.
+    fn get_entities_page(
+        &mut self,
+        _entity_type: &str,
+        _page_size: usize,
+        _cursor: Option<&str>,
+    ) -> Result<EntityPage> {
+        Err(Error::from_unsupported("Paged entity listing is not supported"))
+    }
+
+    fn get_entities_sorted(
+        &mut self,
+        _entity_type: &str,
+        _sort_field: &str,
+        _ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        Err(Error::from_unsupported("Server-side entity sorting is not supported"))
+    }
+
+    fn field_exists(&mut self, _entity_type: &str, _field: &str) -> Result<bool> {
+        Err(Error::from_unsupported("Field existence checks are not supported"))
+    }
+
+    fn get_entity_schema(&mut self, _entity_type: &str) -> Result<EntitySchema> {
+        Err(Error::from_unsupported("Entity schemas are not supported"))
+    }
+
+    /// Reports whether `field` of `entity_type` accepts writes, based on
+    /// the entity schema. Fails if the schema has no such field.
+    fn is_field_writable(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        let schema = self.get_entity_schema(entity_type)?;
+        let field = schema.field(field).ok_or_else(|| {
+            Error::from_database_field(&format!(
+                "Entity type '{}' has no field '{}'",
+                entity_type, field
+            ))
+        })?;
+
+        Ok(!field.read_only)
+    }
+
+    fn notification_backlog(&mut self) -> Result<usize> {
+        Err(Error::from_unsupported("Notification backlog size is not supported"))
+    }
+
+    /// Like `get_notifications`, but asks the server to hold the request
+    /// open for up to `wait` until notifications arrive. Servers that do not
+    /// hold requests simply answer right away.
+    fn get_notifications_longpoll(&mut self, _wait: Duration) -> Result<Vec<Notification>> {
+        Err(Error::from_unsupported("Long-polling for notifications is not supported"))
+    }
+
+    /// Reads only the fields of `requests` written after `since` and returns
+    /// them, letting the server skip unchanged fields.
+    fn read_changed_since(
+        &mut self,
+        _requests: &Vec<Field>,
+        _since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        Err(Error::from_unsupported("Reading changes since a time is not supported"))
+    }
+
+    /// Reads `requests` like `read` and returns each field's value prior to
+    /// its latest write, in the same order, or `None` where the server kept
+    /// no history for it.
+    fn read_with_previous(&mut self, _requests: &Vec<Field>) -> Result<Vec<Option<Field>>> {
+        Err(Error::from_unsupported("Reading previous values is not supported"))
+    }
+
+    /// Registers like `register_notification`, also reporting when the
+    /// registration expires. Clients unaware of expiry report none.
+    fn register_notification_leased(&mut self, config: &Config) -> Result<Lease> {
+        Ok(Lease {
+            token: self.register_notification(config)?,
+            expires_at: None,
+        })
+    }
+}
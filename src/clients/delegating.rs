@@ -0,0 +1,330 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::clients::common::ClientTrait;
+use crate::framework::logger::Logger;
+use crate::Result;
+use crate::schema::field::Field;
+use crate::schema::entity::{Entity, EntityMetadata, EntityPage, EntitySchema};
+use crate::schema::notification::{Notification, Config, Lease, Token};
+use crate::schema::server::ServerInfo;
+
+/// Base for `ClientTrait` decorators. Every method forwards to the client
+/// returned by `inner_mut` unless overridden, and implementors get
+/// `ClientTrait` through the blanket impl below, so a decorator only writes
+/// the methods it changes. New `ClientTrait` methods must be mirrored here.
+///
+/// The methods share their names with `ClientTrait`; with both traits in
+/// scope, call them on a decorator through `ClientTrait::method(&mut c)`.
+pub trait DelegatingClient {
+    fn inner(&self) -> &dyn ClientTrait;
+    fn inner_mut(&mut self) -> &mut dyn ClientTrait;
+
+    fn connect(&mut self) -> Result<()> {
+        self.inner_mut().connect()
+    }
+
+    fn connected(&self) -> bool {
+        self.inner().connected()
+    }
+
+    fn disconnect(&mut self) -> bool {
+        self.inner_mut().disconnect()
+    }
+
+    fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>> {
+        self.inner_mut().get_entities(entity_type)
+    }
+
+    fn get_entity(&mut self, entity_id: &str) -> Result<Entity> {
+        self.inner_mut().get_entity(entity_id)
+    }
+
+    fn get_notifications(&mut self) -> Result<Vec<Notification>> {
+        self.inner_mut().get_notifications()
+    }
+
+    fn read(&mut self, requests: &Vec<Field>) -> Result<()> {
+        self.inner_mut().read(requests)
+    }
+
+    fn register_notification(&mut self, config: &Config) -> Result<Token> {
+        self.inner_mut().register_notification(config)
+    }
+
+    fn set_logger(&mut self, logger: Logger) {
+        self.inner_mut().set_logger(logger)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner_mut().set_timeout(timeout)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner().timeout()
+    }
+
+    fn unregister_notification(&mut self, token: &Token) -> Result<()> {
+        self.inner_mut().unregister_notification(token)
+    }
+
+    fn write(&mut self, requests: &Vec<Field>) -> Result<()> {
+        self.inner_mut().write(requests)
+    }
+
+    fn write_at(&mut self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        self.inner_mut().write_at(requests, write_time)
+    }
+
+    fn server_info(&mut self) -> Result<ServerInfo> {
+        self.inner_mut().server_info()
+    }
+
+    fn get_entity_metadata(&mut self, entity_id: &str) -> Result<EntityMetadata> {
+        self.inner_mut().get_entity_metadata(entity_id)
+    }
+
+    fn get_entities_page(
+        &mut self,
+        entity_type: &str,
+        page_size: usize,
+        cursor: Option<&str>,
+    ) -> Result<EntityPage> {
+        self.inner_mut().get_entities_page(entity_type, page_size, cursor)
+    }
+
+    fn get_entities_sorted(
+        &mut self,
+        entity_type: &str,
+        sort_field: &str,
+        ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        self.inner_mut().get_entities_sorted(entity_type, sort_field, ascending)
+    }
+
+    fn field_exists(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        self.inner_mut().field_exists(entity_type, field)
+    }
+
+    fn get_entity_schema(&mut self, entity_type: &str) -> Result<EntitySchema> {
+        self.inner_mut().get_entity_schema(entity_type)
+    }
+
+    fn is_field_writable(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        self.inner_mut().is_field_writable(entity_type, field)
+    }
+
+    fn notification_backlog(&mut self) -> Result<usize> {
+        self.inner_mut().notification_backlog()
+    }
+
+    fn get_notifications_longpoll(&mut self, wait: Duration) -> Result<Vec<Notification>> {
+        self.inner_mut().get_notifications_longpoll(wait)
+    }
+
+    fn read_changed_since(
+        &mut self,
+        requests: &Vec<Field>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        self.inner_mut().read_changed_since(requests, since)
+    }
+
+    fn read_with_previous(&mut self, requests: &Vec<Field>) -> Result<Vec<Option<Field>>> {
+        self.inner_mut().read_with_previous(requests)
+    }
+
+    fn register_notification_leased(&mut self, config: &Config) -> Result<Lease> {
+        self.inner_mut().register_notification_leased(config)
+    }
+}
+
+impl<T: DelegatingClient> ClientTrait for T {
+    fn connect(&mut self) -> Result<()> {
+        DelegatingClient::connect(self)
+    }
+
+    fn connected(&self) -> bool {
+        DelegatingClient::connected(self)
+    }
+
+    fn disconnect(&mut self) -> bool {
+        DelegatingClient::disconnect(self)
+    }
+
+    fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>> {
+        DelegatingClient::get_entities(self, entity_type)
+    }
+
+    fn get_entity(&mut self, entity_id: &str) -> Result<Entity> {
+        DelegatingClient::get_entity(self, entity_id)
+    }
+
+    fn get_notifications(&mut self) -> Result<Vec<Notification>> {
+        DelegatingClient::get_notifications(self)
+    }
+
+    fn read(&mut self, requests: &Vec<Field>) -> Result<()> {
+        DelegatingClient::read(self, requests)
+    }
+
+    fn register_notification(&mut self, config: &Config) -> Result<Token> {
+        DelegatingClient::register_notification(self, config)
+    }
+
+    fn set_logger(&mut self, logger: Logger) {
+        DelegatingClient::set_logger(self, logger)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        DelegatingClient::set_timeout(self, timeout)
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        DelegatingClient::timeout(self)
+    }
+
+    fn unregister_notification(&mut self, token: &Token) -> Result<()> {
+        DelegatingClient::unregister_notification(self, token)
+    }
+
+    fn write(&mut self, requests: &Vec<Field>) -> Result<()> {
+        DelegatingClient::write(self, requests)
+    }
+
+    fn write_at(&mut self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        DelegatingClient::write_at(self, requests, write_time)
+    }
+
+    fn server_info(&mut self) -> Result<ServerInfo> {
+        DelegatingClient::server_info(self)
+    }
+
+    fn get_entity_metadata(&mut self, entity_id: &str) -> Result<EntityMetadata> {
+        DelegatingClient::get_entity_metadata(self, entity_id)
+    }
+
+    fn get_entities_page(
+        &mut self,
+        entity_type: &str,
+        page_size: usize,
+        cursor: Option<&str>,
+    ) -> Result<EntityPage> {
+        DelegatingClient::get_entities_page(self, entity_type, page_size, cursor)
+    }
+
+    fn get_entities_sorted(
+        &mut self,
+        entity_type: &str,
+        sort_field: &str,
+        ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        DelegatingClient::get_entities_sorted(self, entity_type, sort_field, ascending)
+    }
+
+    fn field_exists(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        DelegatingClient::field_exists(self, entity_type, field)
+    }
+
+    fn get_entity_schema(&mut self, entity_type: &str) -> Result<EntitySchema> {
+        DelegatingClient::get_entity_schema(self, entity_type)
+    }
+
+    fn is_field_writable(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        DelegatingClient::is_field_writable(self, entity_type, field)
+    }
+
+    fn notification_backlog(&mut self) -> Result<usize> {
+        DelegatingClient::notification_backlog(self)
+    }
+
+    fn get_notifications_longpoll(&mut self, wait: Duration) -> Result<Vec<Notification>> {
+        DelegatingClient::get_notifications_longpoll(self, wait)
+    }
+
+    fn read_changed_since(
+        &mut self,
+        requests: &Vec<Field>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        DelegatingClient::read_changed_since(self, requests, since)
+    }
+
+    fn read_with_previous(&mut self, requests: &Vec<Field>) -> Result<Vec<Option<Field>>> {
+        DelegatingClient::read_with_previous(self, requests)
+    }
+
+    fn register_notification_leased(&mut self, config: &Config) -> Result<Lease> {
+        DelegatingClient::register_notification_leased(self, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use crate::schema::field::RawField;
+    use crate::schema::value::RawValue;
+
+    struct Passthrough(Box<dyn ClientTrait>);
+
+    impl DelegatingClient for Passthrough {
+        fn inner(&self) -> &dyn ClientTrait {
+            self.0.as_ref()
+        }
+
+        fn inner_mut(&mut self) -> &mut dyn ClientTrait {
+            self.0.as_mut()
+        }
+    }
+
+    #[test]
+    fn unchanged_methods_forward_to_the_inner_client() {
+        let mock = mock::Client::new();
+        let mut client = Passthrough(Box::new(mock.clone()));
+        let config = Config {
+            entity_id: "Door1".to_string(),
+            entity_type: "Door".to_string(),
+            field: "State".to_string(),
+            notify_on_change: false,
+            context: vec![],
+        };
+
+        ClientTrait::connect(&mut client).unwrap();
+        assert!(ClientTrait::connected(&client));
+
+        ClientTrait::set_timeout(&mut client, Some(Duration::from_secs(3)));
+        assert_eq!(ClientTrait::timeout(&client), Some(Duration::from_secs(3)));
+
+        let field = RawField::new_with_value("Door1", "State", RawValue::from("Open")).into_field();
+        ClientTrait::write(&mut client, &vec![field.clone()]).unwrap();
+        ClientTrait::read(&mut client, &vec![field]).unwrap();
+        let token = ClientTrait::register_notification_leased(&mut client, &config)
+            .unwrap()
+            .token;
+        ClientTrait::unregister_notification(&mut client, &token).unwrap();
+
+        assert_eq!(
+            mock.take_operations(),
+            vec![
+                mock::Operation::Write {
+                    entity_id: "Door1".to_string(),
+                    field: "State".to_string(),
+                    value: RawValue::from("Open"),
+                },
+                mock::Operation::Read {
+                    entity_id: "Door1".to_string(),
+                    field: "State".to_string(),
+                },
+                mock::Operation::RegisterNotification(config),
+                mock::Operation::UnregisterNotification(token),
+            ]
+        );
+
+        assert!(ClientTrait::get_entity_schema(&mut client, "Door")
+            .is_err_and(|e| crate::error::Error::is_unsupported(e.as_ref())));
+        assert!(ClientTrait::disconnect(&mut client));
+        assert!(!mock.connected());
+    }
+}
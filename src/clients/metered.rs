@@ -0,0 +1,264 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::clients::common::ClientTrait;
+use crate::clients::delegating::DelegatingClient;
+use crate::Result;
+use crate::schema::field::Field;
+use crate::schema::entity::{Entity, EntityMetadata, EntityPage, EntitySchema};
+use crate::schema::notification::{Notification, Config, Lease, Token};
+use crate::schema::server::ServerInfo;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    pub calls: u64,
+    pub failures: u64,
+    pub total_time: Duration,
+}
+
+/// Shared handle to the per-operation counters of a metered client. It
+/// stays valid after the client is boxed into a `framework::client::Client`.
+pub struct Metrics(Rc<RefCell<HashMap<&'static str, OperationStats>>>);
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics(Rc::new(RefCell::new(HashMap::new())))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(&self) -> Self {
+        Metrics(self.0.clone())
+    }
+
+    /// Stats of `operation`, named after the `ClientTrait` method.
+    pub fn get(&self, operation: &str) -> OperationStats {
+        self.0.borrow().get(operation).copied().unwrap_or_default()
+    }
+
+    pub fn all(&self) -> HashMap<&'static str, OperationStats> {
+        self.0.borrow().clone()
+    }
+
+    pub fn reset(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    fn record(&self, operation: &'static str, elapsed: Duration, succeeded: bool) {
+        let mut stats = self.0.borrow_mut();
+        let stats = stats.entry(operation).or_default();
+        stats.calls += 1;
+        stats.total_time += elapsed;
+        if !succeeded {
+            stats.failures += 1;
+        }
+    }
+}
+
+/// Counts calls, failures and time spent per request made through the
+/// wrapped client. Local calls such as `connected` are not metered.
+pub struct Client {
+    inner: Box<dyn ClientTrait>,
+    metrics: Metrics,
+}
+
+impl Client {
+    pub fn new(inner: Box<dyn ClientTrait>) -> Self {
+        Self {
+            inner,
+            metrics: Metrics::new(),
+        }
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+
+    fn measure<R>(
+        &mut self,
+        operation: &'static str,
+        f: impl FnOnce(&mut dyn ClientTrait) -> Result<R>,
+    ) -> Result<R> {
+        let start = Instant::now();
+        let result = f(self.inner.as_mut());
+        self.metrics.record(operation, start.elapsed(), result.is_ok());
+        result
+    }
+}
+
+impl DelegatingClient for Client {
+    fn inner(&self) -> &dyn ClientTrait {
+        self.inner.as_ref()
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn ClientTrait {
+        self.inner.as_mut()
+    }
+
+    fn connect(&mut self) -> Result<()> {
+        self.measure("connect", |c| c.connect())
+    }
+
+    fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>> {
+        self.measure("get_entities", |c| c.get_entities(entity_type))
+    }
+
+    fn get_entity(&mut self, entity_id: &str) -> Result<Entity> {
+        self.measure("get_entity", |c| c.get_entity(entity_id))
+    }
+
+    fn get_entity_metadata(&mut self, entity_id: &str) -> Result<EntityMetadata> {
+        self.measure("get_entity_metadata", |c| c.get_entity_metadata(entity_id))
+    }
+
+    fn get_notifications(&mut self) -> Result<Vec<Notification>> {
+        self.measure("get_notifications", |c| c.get_notifications())
+    }
+
+    fn get_notifications_longpoll(&mut self, wait: Duration) -> Result<Vec<Notification>> {
+        self.measure("get_notifications_longpoll", |c| c.get_notifications_longpoll(wait))
+    }
+
+    fn read(&mut self, requests: &Vec<Field>) -> Result<()> {
+        self.measure("read", |c| c.read(requests))
+    }
+
+    fn register_notification(&mut self, config: &Config) -> Result<Token> {
+        self.measure("register_notification", |c| c.register_notification(config))
+    }
+
+    fn register_notification_leased(&mut self, config: &Config) -> Result<Lease> {
+        self.measure("register_notification_leased", |c| c.register_notification_leased(config))
+    }
+
+    fn unregister_notification(&mut self, token: &Token) -> Result<()> {
+        self.measure("unregister_notification", |c| c.unregister_notification(token))
+    }
+
+    fn write(&mut self, requests: &Vec<Field>) -> Result<()> {
+        self.measure("write", |c| c.write(requests))
+    }
+
+    fn write_at(&mut self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        self.measure("write_at", |c| c.write_at(requests, write_time))
+    }
+
+    fn server_info(&mut self) -> Result<ServerInfo> {
+        self.measure("server_info", |c| c.server_info())
+    }
+
+    fn get_entities_page(
+        &mut self,
+        entity_type: &str,
+        page_size: usize,
+        cursor: Option<&str>,
+    ) -> Result<EntityPage> {
+        self.measure("get_entities_page", |c| {
+            c.get_entities_page(entity_type, page_size, cursor)
+        })
+    }
+
+    fn get_entities_sorted(
+        &mut self,
+        entity_type: &str,
+        sort_field: &str,
+        ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        self.measure("get_entities_sorted", |c| {
+            c.get_entities_sorted(entity_type, sort_field, ascending)
+        })
+    }
+
+    fn field_exists(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        self.measure("field_exists", |c| c.field_exists(entity_type, field))
+    }
+
+    fn get_entity_schema(&mut self, entity_type: &str) -> Result<EntitySchema> {
+        self.measure("get_entity_schema", |c| c.get_entity_schema(entity_type))
+    }
+
+    fn is_field_writable(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        self.measure("is_field_writable", |c| c.is_field_writable(entity_type, field))
+    }
+
+    fn notification_backlog(&mut self) -> Result<usize> {
+        self.measure("notification_backlog", |c| c.notification_backlog())
+    }
+
+    fn read_changed_since(
+        &mut self,
+        requests: &Vec<Field>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        self.measure("read_changed_since", |c| c.read_changed_since(requests, since))
+    }
+
+    fn read_with_previous(&mut self, requests: &Vec<Field>) -> Result<Vec<Option<Field>>> {
+        self.measure("read_with_previous", |c| c.read_with_previous(requests))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use crate::schema::entity::FieldSchema;
+    use crate::schema::field::RawField;
+    use crate::schema::value::RawValue;
+
+    #[test]
+    fn every_request_is_counted_with_its_failures() {
+        let mock = mock::Client::new();
+        mock.add_schema(EntitySchema {
+            name: "Door".to_string(),
+            fields: vec![FieldSchema {
+                name: "State".to_string(),
+                type_name: "qdb.String".to_string(),
+                read_only: false,
+            }],
+        });
+        let mut client = Client::new(Box::new(mock.clone()));
+        let metrics = client.metrics();
+        let fields =
+            vec![RawField::new_with_value("Door1", "State", RawValue::from("Open")).into_field()];
+
+        ClientTrait::write(&mut client, &fields).unwrap();
+        ClientTrait::read(&mut client, &fields).unwrap();
+        ClientTrait::read(&mut client, &fields).unwrap();
+        assert!(ClientTrait::is_field_writable(&mut client, "Door", "State").unwrap());
+        assert!(ClientTrait::get_entity_schema(&mut client, "Window").is_err());
+        assert!(ClientTrait::server_info(&mut client).is_err());
+        assert!(ClientTrait::notification_backlog(&mut client).is_err());
+        assert!(ClientTrait::read_with_previous(&mut client, &fields).is_err());
+        // Local calls don't show up
+        ClientTrait::connected(&client);
+
+        let stats = |calls, failures| OperationStats {
+            calls,
+            failures,
+            total_time: Duration::ZERO,
+        };
+        let mut all = metrics.all();
+        for stats in all.values_mut() {
+            stats.total_time = Duration::ZERO;
+        }
+        assert_eq!(
+            all,
+            HashMap::from([
+                ("write", stats(1, 0)),
+                ("read", stats(2, 0)),
+                ("is_field_writable", stats(1, 0)),
+                ("get_entity_schema", stats(1, 1)),
+                ("server_info", stats(1, 1)),
+                ("notification_backlog", stats(1, 1)),
+                ("read_with_previous", stats(1, 1)),
+            ])
+        );
+
+        metrics.reset();
+        assert_eq!(metrics.get("read"), OperationStats::default());
+    }
+}
@@ -0,0 +1,326 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::clients::common::ClientTrait;
+use crate::error::Error;
+use crate::Result;
+use crate::schema::field::Field;
+use crate::schema::entity::{Entity, EntitySchema};
+use crate::schema::notification::{Notification, Config, Lease, Token};
+use crate::schema::value::RawValue;
+
+/// A request made through the mock client, in the order it was made.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Read { entity_id: String, field: String },
+    Write { entity_id: String, field: String, value: RawValue },
+    RegisterNotification(Config),
+    UnregisterNotification(Token),
+}
+
+struct StoredValue {
+    value: RawValue,
+    write_time: DateTime<Utc>,
+    writer_id: String,
+}
+
+struct _Client {
+    connected: bool,
+    timeout: Option<Duration>,
+    entities: Vec<Entity>,
+    schemas: HashMap<String, EntitySchema>,
+    values: HashMap<(String, String), StoredValue>,
+    notifications: Vec<Notification>,
+    next_token: u64,
+    operations: Vec<Operation>,
+    fail_unset_reads: bool,
+    failing_registrations: HashSet<String>,
+    lease_duration: Option<TimeDelta>,
+}
+
+/// In-memory `ClientTrait` for testing workers without a server. Fields
+/// read back what was last written to them, or unspecified values.
+/// Clones share state, so a clone kept by the test can inspect the
+/// operations after the client is boxed into a `framework::client::Client`.
+pub struct Client(Rc<RefCell<_Client>>);
+
+impl Client {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Client(Rc::new(RefCell::new(_Client {
+            connected: false,
+            timeout: None,
+            entities: vec![],
+            schemas: HashMap::new(),
+            values: HashMap::new(),
+            notifications: vec![],
+            next_token: 0,
+            operations: vec![],
+            fail_unset_reads: false,
+            failing_registrations: HashSet::new(),
+            lease_duration: None,
+        })))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(&self) -> Self {
+        Client(self.0.clone())
+    }
+
+    pub fn add_entity(&self, entity: Entity) {
+        self.0.borrow_mut().entities.push(entity);
+    }
+
+    /// Makes `get_entity_schema` report `schema` for its entity type. Types
+    /// without a schema are reported as unsupported.
+    pub fn add_schema(&self, schema: EntitySchema) {
+        self.0.borrow_mut().schemas.insert(schema.name.clone(), schema);
+    }
+
+    /// Sets the stored value of a field without recording an operation.
+    pub fn set_value(&self, entity_id: &str, field: &str, value: RawValue) {
+        self.0.borrow_mut().values.insert(
+            (entity_id.to_string(), field.to_string()),
+            StoredValue {
+                value,
+                write_time: Utc::now(),
+                writer_id: "".to_string(),
+            },
+        );
+    }
+
+    pub fn value(&self, entity_id: &str, field: &str) -> Option<RawValue> {
+        self.0
+            .borrow()
+            .values
+            .get(&(entity_id.to_string(), field.to_string()))
+            .map(|v| v.value.clone())
+    }
+
+    /// Makes reads that include a field never written or set fail, like a
+    /// server asked for a field the entity type doesn't have.
+    pub fn set_fail_unset_reads(&self, fail: bool) {
+        self.0.borrow_mut().fail_unset_reads = fail;
+    }
+
+    /// Makes registrations for notifications on `field` fail.
+    pub fn fail_registrations_of(&self, field: &str) {
+        self.0.borrow_mut().failing_registrations.insert(field.to_string());
+    }
+
+    /// Makes registrations expire `duration` after they are made, or never
+    /// with `None`, the default.
+    pub fn set_lease_duration(&self, duration: Option<TimeDelta>) {
+        self.0.borrow_mut().lease_duration = duration;
+    }
+
+    /// Queues a notification for the next `get_notifications` call.
+    pub fn push_notification(&self, notification: Notification) {
+        self.0.borrow_mut().notifications.push(notification);
+    }
+
+    pub fn operations(&self) -> Vec<Operation> {
+        self.0.borrow().operations.clone()
+    }
+
+    /// Returns the operations recorded so far and clears them.
+    pub fn take_operations(&self) -> Vec<Operation> {
+        std::mem::take(&mut self.0.borrow_mut().operations)
+    }
+
+    /// Panics unless `field` of `entity_id` was written with `expected`.
+    pub fn assert_wrote(&self, entity_id: &str, field: &str, expected: RawValue) {
+        let operations = self.operations();
+        let written = operations.iter().any(|op| {
+            matches!(op, Operation::Write { entity_id: e, field: f, value }
+                if e == entity_id && f == field && *value == expected)
+        });
+
+        assert!(
+            written,
+            "expected a write of {:?} to '{}' of '{}', recorded operations: {:?}",
+            expected, field, entity_id, operations
+        );
+    }
+
+    /// Panics unless `field` of `entity_id` was read.
+    pub fn assert_read(&self, entity_id: &str, field: &str) {
+        let operations = self.operations();
+        let read = operations.iter().any(|op| {
+            matches!(op, Operation::Read { entity_id: e, field: f }
+                if e == entity_id && f == field)
+        });
+
+        assert!(
+            read,
+            "expected a read of '{}' of '{}', recorded operations: {:?}",
+            field, entity_id, operations
+        );
+    }
+
+    /// Panics if any field was written.
+    pub fn assert_no_writes(&self) {
+        let operations = self.operations();
+        assert!(
+            !operations.iter().any(|op| matches!(op, Operation::Write { .. })),
+            "expected no writes, recorded operations: {:?}",
+            operations
+        );
+    }
+}
+
+impl ClientTrait for Client {
+    fn connect(&mut self) -> Result<()> {
+        self.0.borrow_mut().connected = true;
+        Ok(())
+    }
+
+    fn connected(&self) -> bool {
+        self.0.borrow().connected
+    }
+
+    fn disconnect(&mut self) -> bool {
+        self.0.borrow_mut().connected = false;
+        true
+    }
+
+    fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>> {
+        Ok(self
+            .0
+            .borrow()
+            .entities
+            .iter()
+            .filter(|e| e.type_name == entity_type)
+            .cloned()
+            .collect())
+    }
+
+    fn get_entity(&mut self, entity_id: &str) -> Result<Entity> {
+        self.0
+            .borrow()
+            .entities
+            .iter()
+            .find(|e| e.id == entity_id)
+            .cloned()
+            .ok_or(Error::from_client(&format!("Entity '{}' does not exist", entity_id)))
+    }
+
+    fn get_entity_schema(&mut self, entity_type: &str) -> Result<EntitySchema> {
+        self.0
+            .borrow()
+            .schemas
+            .get(entity_type)
+            .cloned()
+            .ok_or(Error::from_unsupported(&format!(
+                "No schema was added for entity type '{}'",
+                entity_type
+            )))
+    }
+
+    fn get_notifications(&mut self) -> Result<Vec<Notification>> {
+        Ok(std::mem::take(&mut self.0.borrow_mut().notifications))
+    }
+
+    fn read(&mut self, requests: &Vec<Field>) -> Result<()> {
+        let mut client = self.0.borrow_mut();
+
+        if client.fail_unset_reads {
+            if let Some(missing) = requests
+                .iter()
+                .find(|r| !client.values.contains_key(&(r.entity_id(), r.name())))
+            {
+                return Err(Error::from_database_field(&format!(
+                    "Field '{}' of entity '{}' does not exist",
+                    missing.name(),
+                    missing.entity_id()
+                )));
+            }
+        }
+
+        for request in requests {
+            client.operations.push(Operation::Read {
+                entity_id: request.entity_id(),
+                field: request.name(),
+            });
+
+            let key = (request.entity_id(), request.name());
+            if let Some(stored) = client.values.get(&key) {
+                request.update_value(stored.value.clone().into_value());
+                request.update_write_time(stored.write_time);
+                request.update_writer_id(&stored.writer_id);
+            } else {
+                request.update_value(RawValue::Unspecified.into_value());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn register_notification(&mut self, config: &Config) -> Result<Token> {
+        let mut client = self.0.borrow_mut();
+        client.operations.push(Operation::RegisterNotification(config.clone()));
+
+        if client.failing_registrations.contains(&config.field) {
+            return Err(Error::from_notification(&format!(
+                "Registration for field '{}' was refused",
+                config.field
+            )));
+        }
+
+        client.next_token += 1;
+        Ok(Token::from(format!("mock-{}", client.next_token)))
+    }
+
+    fn register_notification_leased(&mut self, config: &Config) -> Result<Lease> {
+        let token = self.register_notification(config)?;
+        let expires_at = self.0.borrow().lease_duration.map(|d| Utc::now() + d);
+        Ok(Lease { token, expires_at })
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.0.borrow_mut().timeout = timeout;
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.0.borrow().timeout
+    }
+
+    fn unregister_notification(&mut self, token: &Token) -> Result<()> {
+        self.0
+            .borrow_mut()
+            .operations
+            .push(Operation::UnregisterNotification(token.clone()));
+        Ok(())
+    }
+
+    fn write(&mut self, requests: &Vec<Field>) -> Result<()> {
+        self.write_at(requests, Utc::now())
+    }
+
+    fn write_at(&mut self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        let mut client = self.0.borrow_mut();
+
+        for request in requests {
+            let value = request.value().into_raw();
+            client.operations.push(Operation::Write {
+                entity_id: request.entity_id(),
+                field: request.name(),
+                value: value.clone(),
+            });
+            client.values.insert(
+                (request.entity_id(), request.name()),
+                StoredValue {
+                    value,
+                    write_time,
+                    writer_id: request.writer_id(),
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
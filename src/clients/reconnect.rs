@@ -0,0 +1,42 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Spaces out reconnect attempts across every client sharing the gate, so a
+/// server outage does not make them all reconnect at once. Clones share the
+/// same state and may be handed to clients on other threads.
+#[derive(Clone)]
+pub struct ReconnectGate {
+    interval: Duration,
+    last_attempt: Arc<Mutex<Option<Instant>>>,
+}
+
+impl ReconnectGate {
+    /// Allows at most one attempt per `interval` among all sharing clients.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_attempt: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Claims the next attempt slot if it is due, returning whether the
+    /// caller may attempt to connect now.
+    pub fn try_acquire(&self) -> bool {
+        let mut last_attempt = match self.last_attempt.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let now = Instant::now();
+        if last_attempt.is_some_and(|t| now.duration_since(t) < self.interval) {
+            return false;
+        }
+
+        *last_attempt = Some(now);
+        true
+    }
+}
@@ -0,0 +1,309 @@
+use crate::clients::rest::Pipe;
+use crate::error::Error;
+use crate::framework::logger::Logger;
+use crate::Result;
+
+use flate2::read::GzDecoder;
+use serde_json::{Map, Value};
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::Duration;
+
+pub type Redactor = Box<dyn Fn(&mut Value)>;
+
+fn to_recorded_value(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Decodes a body posted with `content_encoding` back to the text it
+/// carries, so recordings read the same whether or not it was compressed.
+fn decode_body(body: &[u8], content_encoding: &str) -> Result<String> {
+    let mut text = String::new();
+    match content_encoding {
+        "gzip" => {
+            GzDecoder::new(body).read_to_string(&mut text)?;
+        }
+        _ => text = String::from_utf8(body.to_vec())?,
+    }
+
+    Ok(text)
+}
+
+fn from_recorded_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Wraps the transport of a REST client and appends every exchange to a file,
+/// one JSON object per line, so that `ReplayClient` can play the session back.
+pub struct RecordingClient {
+    pipe: Box<dyn Pipe>,
+    file: File,
+    redactor: Option<Redactor>,
+    logger: Option<Logger>,
+}
+
+impl RecordingClient {
+    pub fn new(pipe: Box<dyn Pipe>, path: &str) -> Result<Self> {
+        Ok(Self {
+            pipe,
+            file: File::create(path)?,
+            redactor: None,
+            logger: None,
+        })
+    }
+
+    /// Rewrites recorded requests and responses before they are written out,
+    /// e.g. to blank sensitive values. The live traffic is not affected.
+    pub fn set_redactor(&mut self, redactor: Redactor) {
+        self.redactor = Some(redactor);
+    }
+
+    /// Logs exchanges that could not be recorded. Recording failures never
+    /// fail the live request; without a logger they go unreported.
+    pub fn set_logger(&mut self, logger: Logger) {
+        self.logger = Some(logger);
+    }
+
+    fn record_or_warn(&self, method: &str, url: &str, payload: Option<&str>, response: &Result<String>) {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "record");
+
+        if let Err(e) = self.record(method, url, payload, response) {
+            if let Some(logger) = &self.logger {
+                logger.warning(&format!("[{}] Failed to record {} {}: {}", c, method, url, e));
+            }
+        }
+    }
+
+    fn record(
+        &self,
+        method: &str,
+        url: &str,
+        payload: Option<&str>,
+        response: &Result<String>,
+    ) -> Result<()> {
+        let mut entry = Map::new();
+        entry.insert("method".to_string(), Value::String(method.to_string()));
+        entry.insert("url".to_string(), Value::String(url.to_string()));
+
+        if let Some(payload) = payload {
+            entry.insert("request".to_string(), to_recorded_value(payload));
+        }
+
+        match response {
+            Ok(response) => {
+                entry.insert("response".to_string(), to_recorded_value(response));
+            }
+            Err(e) => {
+                entry.insert("error".to_string(), Value::String(e.to_string()));
+            }
+        }
+
+        let mut entry = Value::Object(entry);
+        if let Some(redactor) = &self.redactor {
+            redactor(&mut entry);
+        }
+
+        writeln!(&self.file, "{}", entry)?;
+        Ok(())
+    }
+}
+
+impl Pipe for RecordingClient {
+    fn post(&self, url: &str, payload: &str) -> Result<String> {
+        let response = self.pipe.post(url, payload);
+        self.record_or_warn("POST", url, Some(payload), &response);
+        response
+    }
+
+    fn get(&self, url: &str) -> Result<String> {
+        let response = self.pipe.get(url);
+        self.record_or_warn("GET", url, None, &response);
+        response
+    }
+
+    fn post_encoded(&self, url: &str, body: &[u8], content_encoding: &str) -> Result<String> {
+        let response = self.pipe.post_encoded(url, body, content_encoding);
+
+        // An unsupported encoding is retried with `post`, which records it
+        if matches!(&response, Err(e) if Error::is_unsupported(e.as_ref())) {
+            return response;
+        }
+
+        // A body that can't be decoded is left out; the exchange still
+        // replays, since replay only matches the method and url
+        let payload = decode_body(body, content_encoding).ok();
+        self.record_or_warn("POST", url, payload.as_deref(), &response);
+        response
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.pipe.set_timeout(timeout);
+    }
+}
+
+/// Serves the exchanges captured by `RecordingClient` back in order. Each call
+/// must match the method and url of the next recorded exchange.
+pub struct ReplayClient {
+    entries: RefCell<VecDeque<Value>>,
+}
+
+impl ReplayClient {
+    pub fn new(path: &str) -> Result<Self> {
+        let mut entries = VecDeque::new();
+
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push_back(serde_json::from_str(&line)?);
+            }
+        }
+
+        Ok(Self {
+            entries: RefCell::new(entries),
+        })
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    fn replay(&self, method: &str, url: &str) -> Result<String> {
+        let entry = self
+            .entries
+            .borrow_mut()
+            .pop_front()
+            .ok_or(Error::from_client("Replay exhausted: no recorded exchange left"))?;
+
+        let recorded_method = entry.get("method").and_then(|v| v.as_str()).unwrap_or("");
+        let recorded_url = entry.get("url").and_then(|v| v.as_str()).unwrap_or("");
+        if recorded_method != method || recorded_url != url {
+            return Err(Error::from_client(&format!(
+                "Replay mismatch: expected {} {}, got {} {}",
+                recorded_method, recorded_url, method, url
+            )));
+        }
+
+        if let Some(error) = entry.get("error") {
+            return Err(Error::from_client(&from_recorded_value(error)));
+        }
+
+        entry
+            .get("response")
+            .map(from_recorded_value)
+            .ok_or(Error::from_client("Replay entry has no recorded response"))
+    }
+}
+
+impl Pipe for ReplayClient {
+    fn post(&self, url: &str, _payload: &str) -> Result<String> {
+        self.replay("POST", url)
+    }
+
+    fn post_encoded(&self, url: &str, _body: &[u8], _content_encoding: &str) -> Result<String> {
+        self.replay("POST", url)
+    }
+
+    fn get(&self, url: &str) -> Result<String> {
+        self.replay("GET", url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use serde_json::json;
+
+    use std::rc::Rc;
+
+    /// Answers every request with a response carrying a session secret, and
+    /// remembers the encodings of the bodies posted to it.
+    struct FakePipe(Rc<RefCell<Vec<String>>>);
+
+    impl Pipe for FakePipe {
+        fn post(&self, _url: &str, _payload: &str) -> Result<String> {
+            self.0.borrow_mut().push("identity".to_string());
+            Ok(json!({ "status": "ok", "secret": "s3cret" }).to_string())
+        }
+
+        fn get(&self, _url: &str) -> Result<String> {
+            Ok(json!({ "header": { "id": "client-1" }, "secret": "s3cret" }).to_string())
+        }
+
+        fn post_encoded(&self, _url: &str, _body: &[u8], content_encoding: &str) -> Result<String> {
+            self.0.borrow_mut().push(content_encoding.to_string());
+            Ok(json!({ "status": "ok", "secret": "s3cret" }).to_string())
+        }
+    }
+
+    fn recording_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("qdb-{}-{}.jsonl", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn recorded_sessions_are_redacted_and_replay_in_order() {
+        let path = recording_path("recording-replay");
+        let mut recorder = RecordingClient::new(Box::new(FakePipe(Rc::default())), &path).unwrap();
+        recorder.set_redactor(Box::new(|entry| {
+            if let Some(secret) = entry.pointer_mut("/response/secret") {
+                *secret = json!("<redacted>");
+            }
+        }));
+
+        let template = recorder.get("http://qdb/auth").unwrap();
+        assert!(template.contains("s3cret"), "live traffic is not redacted");
+        recorder.post("http://qdb/api", r#"{"payload":{}}"#).unwrap();
+        drop(recorder);
+
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("s3cret"), "{}", recorded);
+
+        let replay = ReplayClient::new(&path).unwrap();
+        let template: Value = serde_json::from_str(&replay.get("http://qdb/auth").unwrap()).unwrap();
+        assert_eq!(template["secret"], json!("<redacted>"));
+        assert_eq!(template["header"]["id"], json!("client-1"));
+        assert!(replay.get("http://qdb/api").is_err(), "mismatched method");
+        assert_eq!(replay.remaining(), 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn encoded_posts_are_forwarded_and_recorded_decoded() {
+        let path = recording_path("recording-encoded");
+        let encodings = Rc::new(RefCell::new(vec![]));
+        let recorder = RecordingClient::new(Box::new(FakePipe(encodings.clone())), &path).unwrap();
+
+        recorder
+            .post_encoded("http://qdb/api", &gzip(r#"{"payload":{"id":"Door1"}}"#), "gzip")
+            .unwrap();
+        drop(recorder);
+        assert_eq!(*encodings.borrow(), vec!["gzip".to_string()]);
+
+        let recorded: Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(recorded["request"], json!({ "payload": { "id": "Door1" } }));
+
+        let replay = ReplayClient::new(&path).unwrap();
+        assert!(replay.post_encoded("http://qdb/api", &[], "gzip").is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,25 +1,70 @@
-use crate::error::Error;
+use crate::error::{Error, ResultExt};
 use crate::Result;
 use crate::schema::field::Field;
 use crate::schema::field::RawField;
 use crate::schema::notification::Notification;
 use crate::schema::notification::Config;
+use crate::schema::notification::Lease;
 use crate::schema::notification::Token;
-use crate::schema::entity::Entity;
+use crate::schema::entity::{Entity, EntityMetadata, EntityPage, EntitySchema, FieldSchema};
+use crate::schema::server::ServerInfo;
 use crate::schema::value::DatabaseValue;
 use crate::schema::value::RawValue;
 use crate::clients::common::ClientTrait;
+use crate::clients::reconnect::ReconnectGate;
+use crate::framework::logger::Logger;
 
 use serde_json::Map;
 use serde_json::Number;
 use serde_json::Value;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::Duration;
+
+/// Extra time allowed on top of a long-poll wait before the request is
+/// considered failed, covering transfer and server scheduling delays.
+const LONGPOLL_SLACK: Duration = Duration::from_secs(5);
+
+const DEFAULT_AUTH_PATH: &str = "/make-client-id";
+const DEFAULT_API_PATH: &str = "/api";
+const DEFAULT_STATUS_PATH: &str = "/status";
+const DEFAULT_COMPRESSION_MIN_SIZE: usize = 4096;
 
 pub trait Pipe {
     fn post(&self, url: &str, payload: &str) -> Result<String>;
     fn get(&self, url: &str) -> Result<String>;
+
+    /// Applies a read timeout to subsequent requests. `None` removes it.
+    /// Pipes that cannot bound their requests may ignore this.
+    fn set_timeout(&mut self, _timeout: Option<Duration>) {}
+
+    /// Posts `body` already encoded as `content_encoding`, e.g. "gzip", and
+    /// labels it with a matching `Content-Encoding` header. Pipes that cannot
+    /// do so report it as unsupported and the body is sent unencoded.
+    fn post_encoded(&self, _url: &str, _body: &[u8], _content_encoding: &str) -> Result<String> {
+        Err(Error::from_unsupported("Encoded request bodies are not supported"))
+    }
+}
+
+pub type FieldNameMapper = Box<dyn Fn(&str) -> String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownValuePolicy {
+    Error,
+    AsUnspecified,
+    AsString,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NonFiniteFloatPolicy {
+    Error,
+    Sentinel(f64),
 }
 
 pub struct Client {
@@ -27,7 +72,56 @@ pub struct Client {
     endpoint_reachable: bool,
     request_template: Map<String, Value>,
     url: String,
+    auth_path: String,
+    api_path: String,
+    status_path: String,
     pipe: Box<dyn Pipe>,
+    timeout: Option<Duration>,
+    field_name_mapper: Option<FieldNameMapper>,
+    field_names_from_server: HashMap<String, String>,
+    max_batch_size: Option<usize>,
+    non_finite_float_policy: NonFiniteFloatPolicy,
+    future_write_time_tolerance: Option<Duration>,
+    logger: Option<Logger>,
+    unknown_value_policy: UnknownValuePolicy,
+    reconnect_gate: Option<ReconnectGate>,
+    request_compression: bool,
+    compression_min_size: usize,
+}
+
+/// Fails if the server's write response reports any field as rejected.
+/// Servers omit `success` when it is false, so once any entry reports
+/// success the entries without it count as rejected; responses where no
+/// entry reports it come from servers that don't acknowledge writes.
+fn check_write_acks(response: &Value) -> Result<()> {
+    let Some(acks) = response.get("response").and_then(|v| v.as_array()) else {
+        return Ok(());
+    };
+
+    if !acks.iter().any(|ack| ack.get("success").is_some()) {
+        return Ok(());
+    }
+
+    let rejected = acks
+        .iter()
+        .filter(|ack| ack.get("success").and_then(|v| v.as_bool()) != Some(true))
+        .map(|ack| {
+            format!(
+                "field '{}' of entity '{}'",
+                ack.get("field").and_then(|v| v.as_str()).unwrap_or_default(),
+                ack.get("id").and_then(|v| v.as_str()).unwrap_or_default()
+            )
+        })
+        .collect::<Vec<String>>();
+
+    if rejected.is_empty() {
+        return Ok(());
+    }
+
+    Err(Error::from_database_field(&format!(
+        "Server rejected write of {}",
+        rejected.join(", ")
+    )))
 }
 
 impl Client {
@@ -37,288 +131,253 @@ impl Client {
             auth_failure: false,
             endpoint_reachable: false,
             url: url.to_string(),
+            auth_path: DEFAULT_AUTH_PATH.to_string(),
+            api_path: DEFAULT_API_PATH.to_string(),
+            status_path: DEFAULT_STATUS_PATH.to_string(),
             request_template: Map::new(),
+            timeout: None,
+            field_name_mapper: None,
+            field_names_from_server: HashMap::new(),
+            max_batch_size: None,
+            non_finite_float_policy: NonFiniteFloatPolicy::Error,
+            future_write_time_tolerance: None,
+            logger: None,
+            unknown_value_policy: UnknownValuePolicy::Error,
+            reconnect_gate: None,
+            request_compression: false,
+            compression_min_size: DEFAULT_COMPRESSION_MIN_SIZE,
         }
     }
 
-    fn authenticate(&mut self) -> Result<()> {
-        let response = serde_json::from_str(
-            self.pipe
-                .get(format!("{}/make-client-id", self.url).as_str())?
-                .as_str())?;
-
-        match response {
-            Value::Object(client_id) => {
-                self.request_template = client_id;
-                Ok(())
+    /// Like `new`, but with the client id, API and status endpoints mounted
+    /// at `auth_path`, `api_path` and `status_path` under `url` rather than
+    /// at `/make-client-id`, `/api` and `/status`, as when the server sits
+    /// behind a path-rewriting proxy. A missing leading `/` is added.
+    pub fn with_paths(
+        url: &str,
+        auth_path: &str,
+        api_path: &str,
+        status_path: &str,
+        pipe: Box<dyn Pipe>,
+    ) -> Result<Self> {
+        let normalize = |path: &str| -> Result<String> {
+            match path.trim_start_matches('/') {
+                "" => Err(Error::from_client(&format!("Invalid endpoint path '{}'", path))),
+                path => Ok(format!("/{}", path)),
             }
-            _ => Err(Error::from_client(
-                "Invalid response from server",
-            )),
-        }
+        };
+
+        let mut client = Self::new(url, pipe);
+        client.auth_path = normalize(auth_path)?;
+        client.api_path = normalize(api_path)?;
+        client.status_path = normalize(status_path)?;
+        Ok(client)
     }
 
-    fn has_authenticated(&self, js: &Value) -> bool {
-        js.as_object()
-            .and_then(|o| o.get("header"))
-            .and_then(|v| v.as_object())
-            .and_then(|o| o.get("authenticationStatus"))
-            .and_then(|v| v.as_str())
-            .and_then(|s| Some(s == "AUTHENTICATED"))
-            .unwrap_or(false)
+    /// Makes `connect` consult `gate` first and skip the attempt, staying
+    /// disconnected, when another client sharing it attempted too recently.
+    pub fn with_reconnect_gate(mut self, gate: ReconnectGate) -> Self {
+        self.reconnect_gate = Some(gate);
+        self
     }
 
-    fn parse_database_field(&self, notification: &Value, prefix: &str) -> Result<Field> {
-        let entity_id = notification
-            .pointer(&format!("{}/id", prefix))
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                Error::from_client("Invalid response from server: entity ID is not valid")
-            })?
-            .to_string();
+    /// Gzips request bodies of at least the minimum size (4 KiB unless set
+    /// with `set_compression_min_size`) and sends them with
+    /// `Content-Encoding: gzip`. The server must accept gzipped requests, and
+    /// the pipe must implement `post_encoded`; otherwise bodies go out as is.
+    pub fn set_request_compression(&mut self, enabled: bool) {
+        self.request_compression = enabled;
+    }
 
-        let name = notification
-            .pointer(&format!("{}/name", prefix))
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                Error::from_client("Invalid response from server: name is not valid")
-            })?
-            .to_string();
+    pub fn set_compression_min_size(&mut self, size: usize) {
+        self.compression_min_size = size;
+    }
 
-        let write_time = DateTime::parse_from_rfc3339(
-            notification
-                .pointer(&format!("{}/writeTime", prefix))
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| {
-                    Error::from_client("Invalid response from server: writeTime is not valid")
-                })?,
-        )?
-        .with_timezone(&Utc);
+    fn post(&self, url: &str, body: &str) -> Result<String> {
+        if self.request_compression && body.len() >= self.compression_min_size {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body.as_bytes())?;
 
-        let writer_id = notification
-            .pointer(&format!("{}/writerId", prefix))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+            match self.pipe.post_encoded(url, &encoder.finish()?, "gzip") {
+                Err(e) if Error::is_unsupported(e.as_ref()) => {}
+                result => return result,
+            }
+        }
 
-        let value = Client::extract_value(
-            notification
-                .pointer(&format!("{}/value", prefix))
-                .and_then(|v| v.as_object())
-                .unwrap_or(&Map::new()),
-        )
-        .unwrap_or(RawValue::Unspecified.into_value());
+        self.pipe.post(url, body)
+    }
 
-        Ok(RawField {
-            entity_id,
-            name,
-            write_time,
-            writer_id,
-            value,
-        }.into_field())
+    /// Controls how values with an unrecognized `@type` are read. By default
+    /// they fail the request; they can instead be read as `Unspecified` or as
+    /// a `String` holding the value's raw JSON.
+    pub fn set_unknown_value_policy(&mut self, policy: UnknownValuePolicy) {
+        self.unknown_value_policy = policy;
     }
 
-    fn send(&mut self, payload: &Map<String, Value>) -> Result<Value> {
-        let url = format!("{}/api", self.url);
-        self.endpoint_reachable = false;
-        
-        let mut request = self.request_template.clone();
-        request.insert("payload".to_string(), Value::Object(payload.clone()));
+    /// Warns when a read returns a write time more than `tolerance` ahead of
+    /// the local clock, which usually points at clock skew. `None` (the
+    /// default) disables the check. Reads are never failed by it. Warnings
+    /// go to the client's logger, which a `Database` hands down from its own.
+    pub fn set_future_write_time_tolerance(&mut self, tolerance: Option<Duration>) {
+        self.future_write_time_tolerance = tolerance;
+    }
 
-        let response = serde_json::from_str(
-            self.pipe
-                .post(url.as_str(), serde_json::to_string(&request)?.as_str())?
-                .as_str())?;
+    fn check_write_time(&self, field: &Field) {
+        let (Some(tolerance), Some(logger)) = (self.future_write_time_tolerance, &self.logger) else {
+            return;
+        };
 
-        if !self.has_authenticated(&response) {
-            self.auth_failure = true;
+        let now = Utc::now();
+        let Some(limit) = TimeDelta::from_std(tolerance)
+            .ok()
+            .and_then(|tolerance| now.checked_add_signed(tolerance))
+        else {
+            return;
+        };
 
-            return Err(Error::from_client("Failed to authenticate"));
+        if field.write_time() > limit {
+            let c = format!("{}::{}", std::any::type_name::<Self>(), "read");
+            logger.warning(&format!(
+                "[{}] Field '{}' of entity '{}' has write time {} in the future (now is {})",
+                c,
+                field.name(),
+                field.entity_id(),
+                field.write_time(),
+                now
+            ));
         }
-
-        let response = response.get("payload").ok_or(Error::from_client(
-            "Invalid response from server: payload is not valid",
-        ))?;
-
-        self.endpoint_reachable = true;
-        
-        return Ok(response.clone());
     }
 
-    fn extract_value(value: &Map<String, Value>) -> Result<DatabaseValue> {
-        let value_type = value
-            .get("@type")
-            .and_then(|v| v.as_str())
-            .ok_or(Error::from_client(
-                "Invalid response from server: value type is not valid",
-            ))?;
-
-        let value = match value_type {
-            "type.googleapis.com/qdb.String" => {
-                let value = value
-                    .get("raw")
-                    .and_then(|v| v.as_str())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?
-                    .to_string();
-                RawValue::String(value)
-            }
-            "type.googleapis.com/qdb.Int" => {
-                let value = value
-                    .get("raw")
-                    // should be as i64 but it's a limitation with jsonpb marshaller on server side
-                    .and_then(|v| v.as_str())
-                    .and_then(|v| v.parse::<i64>().ok())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?;
-                RawValue::Integer(value)
-            }
-            "type.googleapis.com/qdb.Float" => {
-                let value = value
-                    .get("raw")
-                    .and_then(|v| v.as_f64())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?;
-                RawValue::Float(value)
-            }
-            "type.googleapis.com/qdb.Bool" => {
-                let value =
-                    value
-                        .get("raw")
-                        .and_then(|v| v.as_bool())
-                        .ok_or(Error::from_client(
-                            "Invalid response from server: value is not valid",
-                        ))?;
-                RawValue::Boolean(value)
-            }
-            "type.googleapis.com/qdb.EntityReference" => {
-                let value = value
-                    .get("raw")
-                    .and_then(|v| v.as_str())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?
-                    .to_string();
-                RawValue::EntityReference(value)
-            }
-            "type.googleapis.com/qdb.Timestamp" => {
-                let value = value
-                    .get("raw")
-                    .and_then(|v| v.as_str())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?;
-                let timestamp = DateTime::parse_from_rfc3339(value)?.to_utc();
-                RawValue::Timestamp(timestamp)
-            }
-            "type.googleapis.com/qdb.ConnectionState" => {
-                let value = value
-                    .get("raw")
-                    .and_then(|v| v.as_str())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?
-                    .to_string();
-                RawValue::ConnectionState(value)
-            }
-            "type.googleapis.com/qdb.GarageDoorState" => {
-                let value = value
-                    .get("raw")
-                    .and_then(|v| v.as_str())
-                    .ok_or(Error::from_client(
-                        "Invalid response from server: value is not valid",
-                    ))?
-                    .to_string();
-                RawValue::GarageDoorState(value)
-            }
-            _ => {
-                return Err(Error::from_client(
-                    "Invalid response from server: value type is not valid",
-                ))
-            }
-        };
-
-        Ok(value.into_value())
+    /// Controls how NaN and infinite floats are written. By default they are
+    /// rejected; a sentinel policy writes the given (finite) value instead.
+    pub fn set_non_finite_float_policy(&mut self, policy: NonFiniteFloatPolicy) {
+        self.non_finite_float_policy = policy;
     }
-}
 
-impl ClientTrait for Client {
-    fn connect(&mut self) -> Result<()> {
-        self.authenticate()?;
+    fn writable_float(&self, value: f64) -> f64 {
+        match self.non_finite_float_policy {
+            NonFiniteFloatPolicy::Sentinel(sentinel) if !value.is_finite() => sentinel,
+            _ => value,
+        }
+    }
 
-        self.auth_failure = false;
-        self.endpoint_reachable = true;
+    /// Splits reads and writes into requests of at most `size` entries.
+    /// A size of zero disables splitting, which is the default.
+    /// Batches are sent in order and are not atomic together: when a write
+    /// batch fails, the earlier batches stay written and the error says how
+    /// many fields they held.
+    pub fn set_max_batch_size(&mut self, size: usize) {
+        self.max_batch_size = if size > 0 { Some(size) } else { None };
+    }
 
-        Ok(())
+    /// Translates field names before they are sent to the server. Names in
+    /// responses are translated back using the names previously sent.
+    pub fn set_field_name_mapper(&mut self, mapper: FieldNameMapper) {
+        self.field_name_mapper = Some(mapper);
+        self.field_names_from_server.clear();
     }
 
-    fn connected(&self) -> bool {
-        self.endpoint_reachable && !self.auth_failure
+    fn map_field_name_for_server(&mut self, name: &str) -> String {
+        match &self.field_name_mapper {
+            Some(mapper) => {
+                let mapped = mapper(name);
+                self.field_names_from_server
+                    .insert(mapped.clone(), name.to_string());
+                mapped
+            }
+            None => name.to_string(),
+        }
     }
 
-    fn disconnect(&mut self) -> bool {
-        self.auth_failure = false;
-        self.endpoint_reachable = false;
-        true
+    fn map_field_name_from_server(&self, name: &str) -> String {
+        self.field_names_from_server
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
     }
 
-    fn get_entity(&mut self, entity_id: &str) -> Result<Entity> {
+    /// Fetches the schema of `entity_type` with field names as the server
+    /// reports them, or `None` if the server does not know the type.
+    fn fetch_entity_schema(&mut self, entity_type: &str) -> Result<Option<EntitySchema>> {
         let mut request = Map::new();
         request.insert(
             "@type".to_string(),
-            Value::String("type.googleapis.com/qdb.WebConfigGetEntityRequest".to_string()),
+            Value::String("type.googleapis.com/qdb.WebConfigGetEntitySchemaRequest".to_string()),
         );
-        request.insert("id".to_string(), Value::String(entity_id.to_string()));
+        request.insert("type".to_string(), Value::String(entity_type.to_string()));
 
         let response = self.send(&request)?;
-        let entity = response
-            .as_object()
-            .and_then(|o| o.get("entity"))
-            .and_then(|v| v.as_object())
+        let schema = match response.as_object().and_then(|o| o.get("schema")) {
+            Some(Value::Object(schema)) => schema,
+            None | Some(Value::Null) => return Ok(None),
+            Some(_) => {
+                return Err(Error::from_client(
+                    "Invalid response from server: schema is not an object",
+                ))
+            }
+        };
+
+        let fields = schema
+            .get("fields")
+            .and_then(|v| v.as_array())
             .ok_or(Error::from_client(
-                "Invalid response from server: Failed to extract entity",
+                "Invalid response from server: schema fields are not valid",
             ))?;
 
-        Ok(Entity {
-            id: entity
-                .get("id")
-                .and_then(|v| v.as_str())
-                .ok_or(Error::from_client(
-                    "Invalid response from server: entity id is not valid",
-                ))?
-                .to_string(),
-            type_name: entity
-                .get("type")
-                .and_then(|v| v.as_str())
-                .ok_or(Error::from_client(
-                    "Invalid response from server: entity type is not valid",
-                ))?
-                .to_string(),
-            name: entity
+        let mut result = EntitySchema {
+            name: schema
                 .get("name")
                 .and_then(|v| v.as_str())
-                .ok_or(Error::from_client(
-                    "Invalid response from server: entity name is not valid",
-                ))?
+                .unwrap_or(entity_type)
                 .to_string(),
-        })
+            fields: Vec::with_capacity(fields.len()),
+        };
+
+        for field in fields {
+            result.fields.push(FieldSchema {
+                name: field
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .ok_or(Error::from_client(
+                        "Invalid response from server: schema field name is not valid",
+                    ))?
+                    .to_string(),
+                type_name: field
+                    .get("type")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                read_only: field
+                    .get("readOnly")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(Some(result))
     }
 
-    fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>> {
+    /// Fetches the raw JSON object of `entity_id`, as the server reports it.
+    fn fetch_entity(&mut self, entity_id: &str) -> Result<Map<String, Value>> {
         let mut request = Map::new();
         request.insert(
             "@type".to_string(),
-            Value::String("type.googleapis.com/qdb.WebRuntimeGetEntitiesRequest".to_string()),
-        );
-        request.insert(
-            "entityType".to_string(),
-            Value::String(entity_type.to_string()),
+            Value::String("type.googleapis.com/qdb.WebConfigGetEntityRequest".to_string()),
         );
+        request.insert("id".to_string(), Value::String(entity_id.to_string()));
 
         let response = self.send(&request)?;
+        response
+            .as_object()
+            .and_then(|o| o.get("entity"))
+            .and_then(|v| v.as_object())
+            .cloned()
+            .ok_or(Error::from_client(
+                "Invalid response from server: Failed to extract entity",
+            ))
+    }
+
+    fn parse_entities(&self, response: &Value) -> Result<Vec<Entity>> {
         let entities = response
             .as_object()
             .and_then(|o| o.get("entities"))
@@ -364,57 +423,326 @@ impl ClientTrait for Client {
         Ok(result)
     }
 
-    fn read(&mut self, requests: &Vec<Field>) -> Result<()> {
-        let mut request = Map::new();
-        request.insert(
-            "@type".to_string(),
-            Value::String("type.googleapis.com/qdb.WebRuntimeDatabaseRequest".to_string()),
-        );
-        request.insert("requestType".to_string(), Value::String("READ".to_string()));
-
-        {
-            let requests = Value::Array(
-                requests
-                    .iter()
-                    .map(|r| {
-                        let mut request = Map::new();
-                        request.insert("id".to_string(), Value::String(r.entity_id()));
-                        request.insert("field".to_string(), Value::String(r.name()));
-                        Value::Object(request)
-                    })
-                    .collect(),
-            );
-            request.insert("requests".to_string(), requests);
-        }
+    fn parse_notifications(&self, response: &Value) -> Result<Vec<Notification>> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "parse_notifications");
 
-        let response = self.send(&request)?;
-        let entities = response
+        let notifications = response
             .as_object()
-            .and_then(|o| o.get("response"))
+            .and_then(|o| o.get("notifications"))
             .and_then(|v| v.as_array())
-            .ok_or(Error::from_client(
-                "Invalid response from server: response is not valid",
-            ))?;
-
-        for entity in entities {
-            match entity {
-                Value::Object(entity) => {
-                    let entity_id = entity
-                        .get("id")
-                        .and_then(|v| v.as_str())
-                        .ok_or(Error::from_client(
-                            "Invalid response from server: entity id is not valid",
-                        ))?
-                        .to_string();
+            .ok_or_else(|| {
+                Error::from_client("Invalid response from server: notifications is not valid")
+            })?;
 
-                    let field_name = entity
-                        .get("field")
-                        .and_then(|v| v.as_str())
-                        .ok_or(Error::from_client(
-                            "Invalid response from server: field name is not valid",
+        let mut result = Vec::with_capacity(notifications.len());
+        for notification in notifications {
+            let token = notification
+                .pointer("/token")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| {
+                    Error::from_client("Invalid response from server: notification token is not valid")
+                })?
+                .to_string();
+
+            let current = self.parse_database_field(notification, "/current")?;
+            let previous = self.parse_database_field(notification, "/previous")?;
+
+            let context = notification
+                .pointer("/context")
+                .and_then(|v| v.as_array())
+                .ok_or_else(|| {
+                    Error::from_client("Invalid response from server: notification context is not valid")
+                })?
+                .iter()
+                .filter_map(|v| match self.parse_database_field(v, "") {
+                    Ok(field) => Some(field),
+                    Err(e) => {
+                        // One malformed context field shouldn't cost the
+                        // whole batch its notifications
+                        if let Some(logger) = &self.logger {
+                            logger.warning(&format!(
+                                "[{}] Skipping malformed context field of notification for field '{}' of entity '{}': {}",
+                                c,
+                                current.name(),
+                                current.entity_id(),
+                                e
+                            ));
+                        }
+                        None
+                    }
+                })
+                .collect();
+
+            result.push(Notification {
+                token,
+                current,
+                previous,
+                context,
+            });
+        }
+
+        Ok(result)
+    }
+
+    fn authenticate(&mut self) -> Result<()> {
+        let response = serde_json::from_str(
+            self.pipe
+                .get(format!("{}{}", self.url, self.auth_path).as_str())?
+                .as_str())?;
+
+        match response {
+            Value::Object(template) => {
+                if !template.get("header").is_some_and(|v| v.is_object()) {
+                    return Err(Error::from_client(
+                        "Invalid response from server: header is missing from request template",
+                    ));
+                }
+
+                if Client::parse_client_id(&template).is_none() {
+                    return Err(Error::from_client(
+                        "Invalid response from server: client id is missing from request template",
+                    ));
+                }
+
+                self.request_template = template;
+                Ok(())
+            }
+            _ => Err(Error::from_client(
+                "Invalid response from server",
+            )),
+        }
+    }
+
+    pub fn client_id(&self) -> Option<String> {
+        Client::parse_client_id(&self.request_template)
+    }
+
+    fn parse_client_id(template: &Map<String, Value>) -> Option<String> {
+        template
+            .get("header")
+            .and_then(|v| v.as_object())
+            .and_then(|o| o.get("id"))
+            .and_then(|v| v.as_str())
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+    }
+
+    fn has_authenticated(&self, js: &Value) -> bool {
+        js.as_object()
+            .and_then(|o| o.get("header"))
+            .and_then(|v| v.as_object())
+            .and_then(|o| o.get("authenticationStatus"))
+            .and_then(|v| v.as_str())
+            .map(|s| s == "AUTHENTICATED")
+            .unwrap_or(false)
+    }
+
+    fn parse_database_field(&self, notification: &Value, prefix: &str) -> Result<Field> {
+        let entity_id = notification
+            .pointer(&format!("{}/id", prefix))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                Error::from_client("Invalid response from server: entity ID is not valid")
+            })?
+            .to_string();
+
+        let name = self.map_field_name_from_server(
+            notification
+                .pointer(&format!("{}/name", prefix))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| {
+                    Error::from_client("Invalid response from server: name is not valid")
+                })?,
+        );
+
+        let write_time = DateTime::parse_from_rfc3339(
+            notification
+                .pointer(&format!("{}/writeTime", prefix))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| {
+                    Error::from_client("Invalid response from server: writeTime is not valid")
+                })?,
+        )?
+        .with_timezone(&Utc);
+
+        let writer_id = notification
+            .pointer(&format!("{}/writerId", prefix))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        let value = self.extract_value(
+            notification
+                .pointer(&format!("{}/value", prefix))
+                .and_then(|v| v.as_object())
+                .unwrap_or(&Map::new()),
+        )
+        .unwrap_or(RawValue::Unspecified.into_value());
+
+        let unit = notification
+            .pointer(&format!("{}/metadata/unit", prefix))
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string());
+
+        let mut field = RawField::new(entity_id, name);
+        field.update_value(value);
+        field.update_write_time(write_time);
+        field.update_writer_id(writer_id.as_str());
+        field.update_unit(unit);
+
+        Ok(field.into_field())
+    }
+
+    /// Sends `payload` and returns the response payload. A response that
+    /// isn't authenticated may come from a stale or partial request
+    /// template, so the template is refreshed once and the request retried
+    /// before giving up.
+    fn send(&mut self, payload: &Map<String, Value>) -> Result<Value> {
+        if let Some(response) = self.try_send(payload)? {
+            return Ok(response);
+        }
+
+        self.refresh_template()?;
+        self.try_send(payload)?.ok_or_else(|| {
+            self.auth_failure = true;
+            Error::from_client("Failed to authenticate").into()
+        })
+    }
+
+    /// Like `send`, but fails instead of retrying when the response isn't
+    /// authenticated, after refreshing the template for later requests.
+    /// Used for writes, which must not be re-sent without the caller
+    /// knowing.
+    fn send_without_retry(&mut self, payload: &Map<String, Value>) -> Result<Value> {
+        if let Some(response) = self.try_send(payload)? {
+            return Ok(response);
+        }
+
+        self.refresh_template()?;
+        Err(Error::from_client(
+            "Request was not authenticated and was not retried",
+        ))
+    }
+
+    fn refresh_template(&mut self) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "refresh_template");
+
+        if let Some(logger) = &self.logger {
+            logger.warning(&format!(
+                "[{}] Request was not authenticated, refreshing request template",
+                c
+            ));
+        }
+
+        if self.authenticate().is_err() {
+            self.auth_failure = true;
+            return Err(Error::from_client("Failed to authenticate"));
+        }
+
+        self.endpoint_reachable = true;
+        Ok(())
+    }
+
+    /// Sends `payload` once, returning `None` if the server did not
+    /// authenticate the request.
+    fn try_send(&mut self, payload: &Map<String, Value>) -> Result<Option<Value>> {
+        let url = format!("{}{}", self.url, self.api_path);
+        self.endpoint_reachable = false;
+        
+        let mut request = self.request_template.clone();
+        request.insert("payload".to_string(), Value::Object(payload.clone()));
+
+        let response = serde_json::from_str(
+            self.post(url.as_str(), serde_json::to_string(&request)?.as_str())?
+                .as_str())?;
+
+        if !self.has_authenticated(&response) {
+            return Ok(None);
+        }
+
+        let response = response.get("payload").ok_or(Error::from_client(
+            "Invalid response from server: payload is not valid",
+        ))?;
+
+        self.endpoint_reachable = true;
+        
+        Ok(Some(response.clone()))
+    }
+
+    fn extract_value(&self, value: &Map<String, Value>) -> Result<DatabaseValue> {
+        let value = match RawValue::from_protobuf_json(&Value::Object(value.clone())) {
+            Err(e) if Error::is_unsupported(e.as_ref()) => match self.unknown_value_policy {
+                UnknownValuePolicy::Error => {
+                    return Err(Error::from_client(
+                        "Invalid response from server: value type is not valid",
+                    ))
+                }
+                UnknownValuePolicy::AsUnspecified => RawValue::Unspecified,
+                UnknownValuePolicy::AsString => {
+                    RawValue::String(Value::Object(value.clone()).to_string())
+                }
+            },
+            result => result.with_context(|| "Invalid response from server".to_string())?,
+        };
+
+        Ok(value.into_value())
+    }
+
+    fn read_batch(&mut self, requests: &[Field]) -> Result<()> {
+        let mut request = Map::new();
+        request.insert(
+            "@type".to_string(),
+            Value::String("type.googleapis.com/qdb.WebRuntimeDatabaseRequest".to_string()),
+        );
+        request.insert("requestType".to_string(), Value::String("READ".to_string()));
+
+        {
+            let requests = Value::Array(
+                requests
+                    .iter()
+                    .map(|r| {
+                        let mut request = Map::new();
+                        request.insert("id".to_string(), Value::String(r.entity_id()));
+                        request.insert(
+                            "field".to_string(),
+                            Value::String(self.map_field_name_for_server(&r.name())),
+                        );
+                        Value::Object(request)
+                    })
+                    .collect(),
+            );
+            request.insert("requests".to_string(), requests);
+        }
+
+        let response = self.send(&request)?;
+        let entities = response
+            .as_object()
+            .and_then(|o| o.get("response"))
+            .and_then(|v| v.as_array())
+            .ok_or(Error::from_client(
+                "Invalid response from server: response is not valid",
+            ))?;
+
+        for entity in entities {
+            match entity {
+                Value::Object(entity) => {
+                    let entity_id = entity
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .ok_or(Error::from_client(
+                            "Invalid response from server: entity id is not valid",
                         ))?
                         .to_string();
 
+                    let field_name = self.map_field_name_from_server(
+                        entity
+                            .get("field")
+                            .and_then(|v| v.as_str())
+                            .ok_or(Error::from_client(
+                                "Invalid response from server: field name is not valid",
+                            ))?,
+                    );
+
                     let field = requests
                         .iter()
                         .find(|r: &&Field| {
@@ -462,9 +790,17 @@ impl ClientTrait for Client {
                         ))?
                         .to_string();
 
-                    field.update_value(Client::extract_value(value)?);
+                    field.update_value(self.extract_value(value)?);
                     field.update_write_time(DateTime::parse_from_rfc3339(write_time)?.to_utc());
                     field.update_writer_id(writer_id.as_str());
+                    field.update_unit(
+                        entity
+                            .get("metadata")
+                            .and_then(|v| v.get("unit"))
+                            .and_then(|v| v.as_str())
+                            .map(|v| v.to_string()),
+                    );
+                    self.check_write_time(field);
                 }
                 _ => {
                     return Err(Box::new(Error::ClientError(
@@ -477,7 +813,33 @@ impl ClientTrait for Client {
         Ok(())
     }
 
-    fn write(&mut self, requests: &Vec<Field>) -> Result<()> {
+    fn write_chunked(
+        &mut self,
+        requests: &[Field],
+        write_time: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        match self.max_batch_size {
+            Some(size) if requests.len() > size => {
+                for (i, chunk) in requests.chunks(size).enumerate() {
+                    self.write_batch(chunk, write_time).with_context(|| {
+                        format!(
+                            "After {} of {} field(s) were written in earlier batches",
+                            i * size,
+                            requests.len()
+                        )
+                    })?;
+                }
+                Ok(())
+            }
+            _ => self.write_batch(requests, write_time),
+        }
+    }
+
+    fn write_batch(
+        &mut self,
+        requests: &[Field],
+        write_time: Option<DateTime<Utc>>,
+    ) -> Result<()> {
         let mut request = Map::new();
         request.insert(
             "@type".to_string(),
@@ -492,128 +854,208 @@ impl ClientTrait for Client {
             let requests = Value::Array(
                 requests
                     .iter()
-                    .map(|r| {
+                    .map(|r| -> Result<Value> {
                         let mut request = Map::new();
                         request.insert("id".to_string(), Value::String(r.entity_id()));
-                        request.insert("field".to_string(), Value::String(r.name()));
-                        let value = match &r.value().into_raw() {
-                            RawValue::String(s) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String("type.googleapis.com/qdb.String".to_string()),
-                                );
-                                value.insert("raw".to_string(), Value::String(s.clone()));
-                                Value::Object(value)
-                            }
-                            RawValue::Integer(i) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String("type.googleapis.com/qdb.Int".to_string()),
-                                );
-                                let n = Number::from(*i);
-                                value.insert("raw".to_string(), Value::Number(n));
-                                Value::Object(value)
-                            }
-                            RawValue::Float(f) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String("type.googleapis.com/qdb.Float".to_string()),
-                                );
-                                let n = Number::from_f64(*f).unwrap_or(Number::from(0));
-                                value.insert("raw".to_string(), Value::Number(n));
-                                Value::Object(value)
-                            }
-                            RawValue::Boolean(b) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String("type.googleapis.com/qdb.Bool".to_string()),
-                                );
-                                value.insert("raw".to_string(), Value::Bool(*b));
-                                Value::Object(value)
-                            }
-                            RawValue::EntityReference(e) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String(
-                                        "type.googleapis.com/qdb.EntityReference".to_string(),
-                                    ),
-                                );
-                                value.insert("raw".to_string(), Value::String(e.clone()));
-                                Value::Object(value)
-                            }
-                            RawValue::Timestamp(t) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String("type.googleapis.com/qdb.Timestamp".to_string()),
-                                );
-                                let seconds = t.timestamp();
-                                let nanos = t.timestamp_subsec_nanos();
-                                let mut raw = Map::new();
-                                raw.insert(
-                                    "seconds".to_string(),
-                                    Value::Number(Number::from(seconds)),
-                                );
-                                raw.insert(
-                                    "nanos".to_string(),
-                                    Value::Number(Number::from(nanos as i64)),
-                                );
-                                value.insert("raw".to_string(), Value::Object(raw));
-                                Value::Object(value)
-                            }
-                            RawValue::ConnectionState(c) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String(
-                                        "type.googleapis.com/qdb.ConnectionState".to_string(),
-                                    ),
-                                );
-                                value.insert("raw".to_string(), Value::String(c.clone()));
-                                Value::Object(value)
-                            }
-                            RawValue::GarageDoorState(g) => {
-                                let mut value = Map::new();
-                                value.insert(
-                                    "@type".to_string(),
-                                    Value::String(
-                                        "type.googleapis.com/qdb.GarageDoorState".to_string(),
-                                    ),
-                                );
-                                value.insert("raw".to_string(), Value::String(g.clone()));
-                                Value::Object(value)
+                        request.insert(
+                            "field".to_string(),
+                            Value::String(self.map_field_name_for_server(&r.name())),
+                        );
+                        if let Some(write_time) = write_time {
+                            let mut raw = Map::new();
+                            raw.insert(
+                                "raw".to_string(),
+                                Value::String(write_time.to_rfc3339()),
+                            );
+                            request.insert("writeTime".to_string(), Value::Object(raw));
+                        }
+                        let value = match r.value().into_raw() {
+                            // The server ignores requests without a value, so
+                            // an unspecified value would be dropped silently
+                            RawValue::Unspecified => {
+                                return Err(Error::from_unsupported(&format!(
+                                    "Cannot clear field '{}' of entity '{}': writing an unspecified value is not supported",
+                                    r.name(),
+                                    r.entity_id()
+                                )))
                             }
-                            _ => Value::Null,
-                        };
+                            RawValue::Float(f) => RawValue::Float(self.writable_float(f)),
+                            value => value,
+                        }
+                        .to_protobuf_json()?;
                         request.insert("value".to_string(), value);
-                        Value::Object(request)
+                        Ok(Value::Object(request))
                     })
-                    .collect(),
+                    .collect::<Result<Vec<Value>>>()?,
             );
             request.insert("requests".to_string(), requests);
         }
 
-        self.send(&request)?;
+        let response = self.send_without_retry(&request)?;
+        check_write_acks(&response)
+    }
+}
+
+impl ClientTrait for Client {
+    fn connect(&mut self) -> Result<()> {
+        // Skipping the attempt isn't a failure; callers see through
+        // `connected` that the client is still disconnected.
+        if self.reconnect_gate.as_ref().is_some_and(|gate| !gate.try_acquire()) {
+            return Ok(());
+        }
+
+        self.authenticate()?;
+
+        self.auth_failure = false;
+        self.endpoint_reachable = true;
 
         Ok(())
     }
 
+    fn connected(&self) -> bool {
+        self.endpoint_reachable && !self.auth_failure
+    }
+
+    fn disconnect(&mut self) -> bool {
+        self.auth_failure = false;
+        self.endpoint_reachable = false;
+        true
+    }
+
+    fn get_entity(&mut self, entity_id: &str) -> Result<Entity> {
+        let entity = self.fetch_entity(entity_id)?;
+
+        Ok(Entity {
+            id: entity
+                .get("id")
+                .and_then(|v| v.as_str())
+                .ok_or(Error::from_client(
+                    "Invalid response from server: entity id is not valid",
+                ))?
+                .to_string(),
+            type_name: entity
+                .get("type")
+                .and_then(|v| v.as_str())
+                .ok_or(Error::from_client(
+                    "Invalid response from server: entity type is not valid",
+                ))?
+                .to_string(),
+            name: entity
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or(Error::from_client(
+                    "Invalid response from server: entity name is not valid",
+                ))?
+                .to_string(),
+        })
+    }
+
+    fn get_entity_metadata(&mut self, entity_id: &str) -> Result<EntityMetadata> {
+        let entity = self.fetch_entity(entity_id)?;
+        let timestamp = |key: &str| -> Result<Option<DateTime<Utc>>> {
+            match entity.get(key).and_then(|v| v.as_str()) {
+                Some(t) => Ok(Some(DateTime::parse_from_rfc3339(t)?.to_utc())),
+                None => Ok(None),
+            }
+        };
+
+        Ok(EntityMetadata {
+            created_at: timestamp("createdAt")?,
+            modified_at: timestamp("modifiedAt")?,
+            created_by: entity
+                .get("createdBy")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+        })
+    }
+
+    fn get_entities(&mut self, entity_type: &str) -> Result<Vec<Entity>> {
+        let mut request = Map::new();
+        request.insert(
+            "@type".to_string(),
+            Value::String("type.googleapis.com/qdb.WebRuntimeGetEntitiesRequest".to_string()),
+        );
+        request.insert(
+            "entityType".to_string(),
+            Value::String(entity_type.to_string()),
+        );
+
+        let response = self.send(&request)?;
+        self.parse_entities(&response)
+    }
+
+    fn get_entities_page(
+        &mut self,
+        entity_type: &str,
+        page_size: usize,
+        cursor: Option<&str>,
+    ) -> Result<EntityPage> {
+        let mut request = Map::new();
+        request.insert(
+            "@type".to_string(),
+            Value::String("type.googleapis.com/qdb.WebRuntimeGetEntitiesRequest".to_string()),
+        );
+        request.insert(
+            "entityType".to_string(),
+            Value::String(entity_type.to_string()),
+        );
+        request.insert(
+            "pageSize".to_string(),
+            Value::Number(Number::from(page_size)),
+        );
+        if let Some(cursor) = cursor {
+            request.insert("cursor".to_string(), Value::String(cursor.to_string()));
+        }
+
+        // Servers without paging ignore the extra keys and return every
+        // entity without a cursor, which reads as a single final page.
+        let response = self.send(&request)?;
+        Ok(EntityPage {
+            entities: self.parse_entities(&response)?,
+            next_cursor: response
+                .get("nextCursor")
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string()),
+        })
+    }
+
+    fn read(&mut self, requests: &Vec<Field>) -> Result<()> {
+        match self.max_batch_size {
+            Some(size) if requests.len() > size => {
+                for chunk in requests.chunks(size) {
+                    self.read_batch(chunk)?;
+                }
+                Ok(())
+            }
+            _ => self.read_batch(requests),
+        }
+    }
+
+    fn write(&mut self, requests: &Vec<Field>) -> Result<()> {
+        self.write_chunked(requests, None)
+    }
+
+    fn write_at(&mut self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        self.write_chunked(requests, Some(write_time))
+    }
+
     fn register_notification(&mut self, config: &Config) -> Result<Token> {
+        Ok(self.register_notification_leased(config)?.token)
+    }
+
+    fn register_notification_leased(&mut self, config: &Config) -> Result<Lease> {
         let context = config
             .context
             .iter()
-            .map(|v| Value::String(v.into()))
+            .map(|v| Value::String(self.map_field_name_for_server(v)))
             .collect();
+        let field = self.map_field_name_for_server(&config.field);
 
         let mut notification = Map::new();
         notification.insert("id".to_string(), Value::String(config.entity_id.clone()));
         notification.insert("type".to_string(), Value::String(config.entity_type.clone()));
-        notification.insert("field".to_string(), Value::String(config.field.clone()));
+        notification.insert("field".to_string(), Value::String(field));
         notification.insert(
             "notifyOnChange".to_string(),
             Value::Bool(config.notify_on_change),
@@ -640,7 +1082,7 @@ impl ClientTrait for Client {
             .ok_or(Error::from_client(
                 "Invalid response from server: token is not valid",
             ))?
-            .get(0)
+            .first()
             .ok_or(Error::from_client(
                 "Invalid response from server: token is not valid",
             ))?
@@ -649,7 +1091,90 @@ impl ClientTrait for Client {
                 "Invalid response from server: token is not valid",
             ))?;
 
-        Ok(Token::from(token))
+        let expires_at = match response.pointer("/expiries/0").and_then(|v| v.as_str()) {
+            Some(expiry) => Some(DateTime::parse_from_rfc3339(expiry)?.to_utc()),
+            None => None,
+        };
+
+        Ok(Lease {
+            token: Token::from(token),
+            expires_at,
+        })
+    }
+
+    fn server_info(&mut self) -> Result<ServerInfo> {
+        let response: Value = serde_json::from_str(
+            self.pipe
+                .get(format!("{}{}", self.url, self.status_path).as_str())?
+                .as_str())?;
+
+        let version = response
+            .get("version")
+            .and_then(|v| v.as_str())
+            .ok_or(Error::from_client(
+                "Invalid response from server: version is not valid",
+            ))?
+            .to_string();
+
+        let uptime = response
+            .get("uptimeSeconds")
+            .and_then(|v| v.as_f64())
+            .and_then(|v| Duration::try_from_secs_f64(v).ok());
+
+        let entity_counts = response
+            .get("entityCounts")
+            .and_then(|v| v.as_object())
+            .map(|counts| {
+                counts
+                    .iter()
+                    .filter_map(|(entity_type, count)| {
+                        count.as_u64().map(|count| (entity_type.clone(), count))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ServerInfo {
+            version,
+            uptime,
+            entity_counts,
+        })
+    }
+
+    fn field_exists(&mut self, entity_type: &str, field: &str) -> Result<bool> {
+        let field = self.map_field_name_for_server(field);
+
+        Ok(self
+            .fetch_entity_schema(entity_type)?
+            .is_some_and(|schema| schema.has_field(&field)))
+    }
+
+    fn get_entity_schema(&mut self, entity_type: &str) -> Result<EntitySchema> {
+        let mut schema = self
+            .fetch_entity_schema(entity_type)?
+            .ok_or(Error::from_client(&format!(
+                "Entity type '{}' has no schema",
+                entity_type
+            )))?;
+
+        for field in &mut schema.fields {
+            field.name = self.map_field_name_from_server(&field.name);
+        }
+
+        Ok(schema)
+    }
+
+    fn set_logger(&mut self, logger: Logger) {
+        self.logger = Some(logger);
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+        self.pipe.set_timeout(timeout);
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
     }
 
     fn unregister_notification(&mut self, token: &Token) -> Result<()> {
@@ -670,6 +1195,27 @@ impl ClientTrait for Client {
         Ok(())
     }
 
+    fn notification_backlog(&mut self) -> Result<usize> {
+        let mut request = Map::new();
+        request.insert(
+            "@type".to_string(),
+            Value::String(
+                "type.googleapis.com/qdb.WebRuntimeGetNotificationBacklogRequest".to_string(),
+            ),
+        );
+
+        let response = self.send(&request)?;
+        let count = response
+            .as_object()
+            .and_then(|o| o.get("count"))
+            .and_then(|v| v.as_u64())
+            .ok_or(Error::from_client(
+                "Invalid response from server: notification backlog count is not valid",
+            ))?;
+
+        Ok(count as usize)
+    }
+
     fn get_notifications(&mut self) -> Result<Vec<Notification>> {
         let mut request = Map::new();
         request.insert(
@@ -678,45 +1224,284 @@ impl ClientTrait for Client {
         );
 
         let response = self.send(&request)?;
-        let notifications = response
-            .as_object()
-            .and_then(|o| o.get("notifications"))
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| {
-                Error::from_client("Invalid response from server: notifications is not valid")
-            })?;
+        self.parse_notifications(&response)
+    }
 
-        let mut result = Vec::with_capacity(notifications.len());
-        for notification in notifications {
-            let token = notification
-                .pointer("/token")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| {
-                    Error::from_client("Invalid response from server: notification token is not valid")
-                })?
-                .to_string();
+    fn get_notifications_longpoll(&mut self, wait: Duration) -> Result<Vec<Notification>> {
+        let mut request = Map::new();
+        request.insert(
+            "@type".to_string(),
+            Value::String("type.googleapis.com/qdb.WebRuntimeGetNotificationsRequest".to_string()),
+        );
+        request.insert(
+            "waitMs".to_string(),
+            Value::Number(Number::from(wait.as_millis() as u64)),
+        );
 
-            let current = self.parse_database_field(notification, "/current")?;
-            let previous = self.parse_database_field(notification, "/previous")?;
+        let previous = self.timeout;
+        self.pipe.set_timeout(Some(wait + LONGPOLL_SLACK));
+        let response = self.send(&request);
+        self.pipe.set_timeout(previous);
 
-            let context = notification
-                .pointer("/context")
-                .and_then(|v| v.as_array())
-                .ok_or_else(|| {
-                    Error::from_client("Invalid response from server: notification context is not valid")
-                })?
-                .iter()
-                .map(|v| self.parse_database_field(v, ""))
-                .collect::<Result<Vec<Field>>>()?;
+        self.parse_notifications(&response?)
+    }
+}
 
-            result.push(Notification {
-                token,
-                current,
-                previous,
-                context,
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    /// Server side of `FakePipe`: the request template handed out on
+    /// authentication, queued API responses, and the API requests received.
+    struct FakeServer {
+        template: Value,
+        responses: VecDeque<Value>,
+        requests: Vec<Value>,
+    }
+
+    struct FakePipe(Rc<RefCell<FakeServer>>);
+
+    impl Pipe for FakePipe {
+        fn post(&self, _url: &str, payload: &str) -> Result<String> {
+            let mut server = self.0.borrow_mut();
+            server.requests.push(serde_json::from_str(payload)?);
+            Ok(server
+                .responses
+                .pop_front()
+                .ok_or(Error::from_client("No response queued"))?
+                .to_string())
         }
 
-        Ok(result)
+        fn get(&self, _url: &str) -> Result<String> {
+            Ok(self.0.borrow().template.to_string())
+        }
+    }
+
+    fn authenticated(payload: Value) -> Value {
+        json!({
+            "header": { "authenticationStatus": "AUTHENTICATED" },
+            "payload": payload,
+        })
+    }
+
+    fn connected_client() -> (Rc<RefCell<FakeServer>>, Client) {
+        let server = Rc::new(RefCell::new(FakeServer {
+            template: json!({ "header": { "id": "client-1" } }),
+            responses: VecDeque::new(),
+            requests: vec![],
+        }));
+
+        let mut client = Client::new("http://qdb", Box::new(FakePipe(server.clone())));
+        client.connect().unwrap();
+        (server, client)
+    }
+
+    fn float_field(value: f64) -> Vec<Field> {
+        vec![RawField::new_with_value("Sensor1", "Reading", RawValue::Float(value)).into_field()]
+    }
+
+    #[test]
+    fn write_rejects_non_finite_floats() {
+        let (server, mut client) = connected_client();
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let e = client.write(&float_field(value)).unwrap_err();
+            assert!(e.to_string().contains("non-finite float"), "{}: {}", value, e);
+        }
+
+        assert!(server.borrow().requests.is_empty());
+    }
+
+    #[test]
+    fn write_maps_non_finite_floats_to_sentinel() {
+        let (server, mut client) = connected_client();
+        client.set_non_finite_float_policy(NonFiniteFloatPolicy::Sentinel(-1.0));
+
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            server.borrow_mut().responses.push_back(authenticated(json!({})));
+            client.write(&float_field(value)).unwrap();
+
+            let request = server.borrow_mut().requests.pop().unwrap();
+            assert_eq!(request.pointer("/payload/requests/0/value/raw"), Some(&json!(-1.0)));
+        }
+    }
+
+    #[test]
+    fn gated_connect_succeeds_without_connecting() {
+        let (_server, client) = connected_client();
+        let mut client = client.with_reconnect_gate(ReconnectGate::new(Duration::from_secs(3600)));
+
+        client.disconnect();
+        client.connect().unwrap();
+        client.disconnect();
+        client.connect().unwrap();
+
+        assert!(!client.connected());
+    }
+
+    fn field_json(name: &str, raw: &str) -> Value {
+        json!({
+            "id": "Door1",
+            "name": name,
+            "writeTime": "2024-05-01T08:30:00Z",
+            "value": { "@type": "type.googleapis.com/qdb.String", "raw": raw },
+        })
+    }
+
+    #[test]
+    fn get_notifications_skips_malformed_context_fields() {
+        let (server, mut client) = connected_client();
+        let mut malformed = field_json("Alarm", "Armed");
+        malformed.as_object_mut().unwrap().remove("writeTime");
+
+        server.borrow_mut().responses.push_back(authenticated(json!({
+            "notifications": [{
+                "token": "token-1",
+                "current": field_json("State", "Open"),
+                "previous": field_json("State", "Closed"),
+                "context": [field_json("Lock", "Locked"), malformed, field_json("Name", "Front")],
+            }],
+        })));
+
+        let notifications = client.get_notifications().unwrap();
+        assert_eq!(notifications.len(), 1);
+
+        let context: Vec<String> = notifications[0].context.iter().map(|f| f.name()).collect();
+        assert_eq!(context, vec!["Lock".to_string(), "Name".to_string()]);
+        assert_eq!(
+            notifications[0].context_field("Lock").unwrap().value().into_raw(),
+            RawValue::from("Locked")
+        );
+    }
+
+    #[test]
+    fn check_write_acks_names_rejected_fields() {
+        let response = json!({
+            "response": [
+                { "id": "Door1", "field": "State", "success": true },
+                { "id": "Door1", "field": "Alarm", "success": false },
+            ],
+        });
+
+        let e = check_write_acks(&response).unwrap_err().to_string();
+        assert!(e.contains("field 'Alarm' of entity 'Door1'"), "{}", e);
+        assert!(!e.contains("State"), "{}", e);
+
+        let response = json!({ "response": [{ "id": "Door1", "field": "State", "success": true }] });
+        assert!(check_write_acks(&response).is_ok());
+    }
+
+    #[test]
+    fn write_fails_when_the_server_rejects_a_field() {
+        let (server, mut client) = connected_client();
+        server.borrow_mut().responses.push_back(authenticated(json!({
+            "response": [{ "id": "Sensor1", "field": "Reading", "success": false }],
+        })));
+
+        let e = client.write(&float_field(1.5)).unwrap_err().to_string();
+        assert!(e.contains("field 'Reading' of entity 'Sensor1'"), "{}", e);
+    }
+
+    fn client_with_template(template: Value) -> Client {
+        let server = Rc::new(RefCell::new(FakeServer {
+            template,
+            responses: VecDeque::new(),
+            requests: vec![],
+        }));
+
+        Client::new("http://qdb", Box::new(FakePipe(server)))
+    }
+
+    #[test]
+    fn connect_fails_on_an_incomplete_template() {
+        for template in [json!({}), json!({ "header": "client-1" }), json!({ "header": {} })] {
+            let mut client = client_with_template(template.clone());
+            assert!(client.connect().is_err(), "{}", template);
+            assert!(!client.connected(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn unauthenticated_reads_are_retried_once() {
+        let (server, mut client) = connected_client();
+        let unauthenticated = json!({ "header": { "authenticationStatus": "UNAUTHENTICATED" } });
+        server.borrow_mut().responses.extend([
+            unauthenticated.clone(),
+            authenticated(json!({ "notifications": [] })),
+        ]);
+
+        assert!(client.get_notifications().unwrap().is_empty());
+        assert_eq!(server.borrow().requests.len(), 2);
+
+        server.borrow_mut().responses.extend([unauthenticated.clone(), unauthenticated]);
+        assert!(client.get_notifications().is_err());
+        assert_eq!(server.borrow().requests.len(), 4);
+        assert!(!client.connected());
+    }
+
+    #[test]
+    fn unauthenticated_writes_are_not_retried() {
+        let (server, mut client) = connected_client();
+        server.borrow_mut().responses.push_back(json!({
+            "header": { "authenticationStatus": "UNAUTHENTICATED" },
+        }));
+
+        let e = client.write(&float_field(1.5)).unwrap_err().to_string();
+        assert!(e.contains("not retried"), "{}", e);
+        assert_eq!(server.borrow().requests.len(), 1);
+        assert!(client.connected());
+    }
+
+    #[test]
+    fn write_rejects_unspecified_values_without_sending() {
+        let (server, mut client) = connected_client();
+        let fields = vec![
+            RawField::new_with_value("Sensor1", "Reading", RawValue::Float(1.5)).into_field(),
+            RawField::new("Sensor1", "Label").into_field(),
+        ];
+
+        let e = client.write(&fields).unwrap_err();
+        assert!(Error::is_unsupported(e.as_ref()), "{}", e);
+        assert!(e.to_string().contains("field 'Label' of entity 'Sensor1'"), "{}", e);
+        assert!(server.borrow().requests.is_empty());
+    }
+
+    /// Answers GETs with a status document and records the urls asked for.
+    struct StatusPipe(Rc<RefCell<Vec<String>>>);
+
+    impl Pipe for StatusPipe {
+        fn post(&self, _url: &str, _payload: &str) -> Result<String> {
+            Err(Error::from_client("Unexpected post"))
+        }
+
+        fn get(&self, url: &str) -> Result<String> {
+            self.0.borrow_mut().push(url.to_string());
+            Ok(json!({ "version": "1.2.3" }).to_string())
+        }
+    }
+
+    #[test]
+    fn server_info_honors_path_overrides() {
+        let urls = Rc::new(RefCell::new(vec![]));
+
+        let mut client = Client::new("http://qdb", Box::new(StatusPipe(urls.clone())));
+        assert_eq!(client.server_info().unwrap().version, "1.2.3");
+
+        let pipe = Box::new(StatusPipe(urls.clone()));
+        let mut client =
+            Client::with_paths("http://qdb", "qdb/auth", "qdb/api", "qdb/health", pipe).unwrap();
+        client.server_info().unwrap();
+
+        assert_eq!(
+            *urls.borrow(),
+            vec!["http://qdb/status".to_string(), "http://qdb/qdb/health".to_string()]
+        );
+        assert!(Client::with_paths("http://qdb", "auth", "api", "/", Box::new(StatusPipe(urls))).is_err());
     }
 }
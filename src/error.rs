@@ -1,8 +1,13 @@
 #[derive(Debug)]
 pub enum Error {
     ClientError(String),
+    ConflictingWrites(String),
+    Context(String, Box<dyn std::error::Error>),
     DatabaseFieldError(String),
     NotificationError(String),
+    Timeout(String),
+    Unsupported(String),
+    VerificationFailed(String),
 }
 
 impl Error {
@@ -10,6 +15,16 @@ impl Error {
         Box::new(Error::ClientError(msg.to_string()))
     }
 
+    pub fn from_conflicting_writes(msg: &str) -> Box<Self> {
+        Box::new(Error::ConflictingWrites(msg.to_string()))
+    }
+
+    /// Wraps `err` with a description of what was being attempted. The
+    /// wrapped error stays reachable through `source()`.
+    pub fn with_context(err: Box<dyn std::error::Error>, context: &str) -> Box<Self> {
+        Box::new(Error::Context(context.to_string(), err))
+    }
+
     pub fn from_notification(msg: &str) -> Box<Self> {
         Box::new(Error::NotificationError(msg.to_string()))
     }
@@ -17,14 +32,66 @@ impl Error {
     pub fn from_database_field(msg: &str) -> Box<Self> {
         Box::new(Error::DatabaseFieldError(msg.to_string()))
     }
+
+    pub fn from_timeout(msg: &str) -> Box<Self> {
+        Box::new(Error::Timeout(msg.to_string()))
+    }
+
+    pub fn from_unsupported(msg: &str) -> Box<Self> {
+        Box::new(Error::Unsupported(msg.to_string()))
+    }
+
+    pub fn from_verification_failed(msg: &str) -> Box<Self> {
+        Box::new(Error::VerificationFailed(msg.to_string()))
+    }
+
+    pub fn is_timeout(err: &(dyn std::error::Error + 'static)) -> bool {
+        Error::any_in_chain(err, |e| matches!(e, Error::Timeout(_)))
+    }
+
+    pub fn is_unsupported(err: &(dyn std::error::Error + 'static)) -> bool {
+        Error::any_in_chain(err, |e| matches!(e, Error::Unsupported(_)))
+    }
+
+    fn any_in_chain(
+        err: &(dyn std::error::Error + 'static),
+        predicate: fn(&Error) -> bool,
+    ) -> bool {
+        let mut current = Some(err);
+        while let Some(err) = current {
+            if err.downcast_ref::<Error>().is_some_and(predicate) {
+                return true;
+            }
+
+            current = err.source();
+        }
+
+        false
+    }
+}
+
+pub trait ResultExt<T> {
+    /// Adds context to the error, if any, built lazily by `context`.
+    fn with_context(self, context: impl FnOnce() -> String) -> crate::Result<T>;
+}
+
+impl<T> ResultExt<T> for crate::Result<T> {
+    fn with_context(self, context: impl FnOnce() -> String) -> crate::Result<T> {
+        self.map_err(|e| Error::with_context(e, &context()) as Box<dyn std::error::Error>)
+    }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::ClientError(msg) => write!(f, "Client error: {}", msg),
+            Error::ConflictingWrites(msg) => write!(f, "Conflicting writes: {}", msg),
+            Error::Context(context, err) => write!(f, "{}: {}", context, err),
             Error::DatabaseFieldError(msg) => write!(f, "Database error: {}", msg),
             Error::NotificationError(msg) => write!(f, "Notification error: {}", msg),
+            Error::Timeout(msg) => write!(f, "Timeout error: {}", msg),
+            Error::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            Error::VerificationFailed(msg) => write!(f, "Verification failed: {}", msg),
         }
     }
 }
@@ -33,8 +100,13 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Error::ClientError(_) => None,
+            Error::ConflictingWrites(_) => None,
+            Error::Context(_, err) => Some(err.as_ref()),
             Error::DatabaseFieldError(_) => None,
             Error::NotificationError(_) => None,
+            Error::Timeout(_) => None,
+            Error::Unsupported(_) => None,
+            Error::VerificationFailed(_) => None,
         }
     }
 }
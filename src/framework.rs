@@ -1,8 +1,11 @@
 
 pub mod application;
+pub mod batch;
 pub mod client;
+pub mod config;
 pub mod database;
 pub mod events;
 pub mod logger;
 pub mod notification;
-pub mod workers;
\ No newline at end of file
+pub mod util;
+pub mod workers;
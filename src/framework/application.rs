@@ -16,6 +16,7 @@ type _BoolFlag = Rc<RefCell<bool>>;
 pub struct BoolFlag(_BoolFlag);
 
 impl BoolFlag {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         BoolFlag(Rc::new(RefCell::new(false)))
     }
@@ -46,6 +47,7 @@ pub struct Context(ContextRef);
 
 impl Context {
     pub fn new(database: Database, logger: Logger) -> Self {
+        database.set_logger(logger.clone());
         Context(Rc::new(RefCell::new(_Context {
             database,
             logger,
@@ -64,6 +66,17 @@ impl Context {
     pub fn quit(&self) -> BoolFlag {
         self.0.borrow().quit.clone()
     }
+
+    /// Returns a context sharing this one's database and quit flag, whose
+    /// logger tags every message with `scope`.
+    pub fn scoped(&self, scope: &str) -> Context {
+        let inner = self.0.borrow();
+        Context(Rc::new(RefCell::new(_Context {
+            database: inner.database.clone(),
+            logger: inner.logger.scoped(scope),
+            quit: inner.quit.clone(),
+        })))
+    }
 }
 
 impl Clone for Context {
@@ -72,10 +85,42 @@ impl Clone for Context {
     }
 }
 
+/// What `Application` does when a worker's `do_work` keeps failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Keep calling the worker every tick regardless of failures.
+    Ignore,
+    /// Deinitialize and reinitialize the worker after `max_failures`
+    /// consecutive failed ticks.
+    Restart { max_failures: usize },
+    /// Deinitialize the worker and stop calling it after `max_failures`
+    /// consecutive failed ticks.
+    Disable { max_failures: usize },
+}
+
+struct SupervisedWorker {
+    worker: Box<dyn WorkerTrait>,
+    policy: RestartPolicy,
+    consecutive_failures: usize,
+    disabled: bool,
+    ctx: Option<Context>,
+}
+
+impl SupervisedWorker {
+    /// Returns the context scoped to this worker, built from `ctx` the
+    /// first time and reused after that.
+    fn scoped(&mut self, ctx: &Context) -> Context {
+        self.ctx
+            .get_or_insert_with(|| ctx.scoped(self.worker.name()))
+            .clone()
+    }
+}
+
 pub struct Application {
     ctx: Context,
-    workers: Vec<Box<dyn WorkerTrait>>,
+    workers: Vec<SupervisedWorker>,
     loop_interval_ms: u64,
+    deinitialized: bool,
 }
 
 impl Application {
@@ -84,6 +129,90 @@ impl Application {
             ctx,
             workers: vec![],
             loop_interval_ms,
+            deinitialized: false,
+        }
+    }
+
+    pub fn add_worker_with_policy(&mut self, worker: Box<dyn WorkerTrait>, policy: RestartPolicy) {
+        self.workers.push(SupervisedWorker {
+            worker,
+            policy,
+            consecutive_failures: 0,
+            disabled: false,
+            ctx: None,
+        });
+    }
+
+    fn supervise(&mut self, ctx: &Context, index: usize, succeeded: bool) {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "supervise");
+        let supervised = &mut self.workers[index];
+
+        if succeeded {
+            supervised.consecutive_failures = 0;
+            return;
+        }
+
+        supervised.consecutive_failures += 1;
+        match supervised.policy {
+            RestartPolicy::Ignore => {}
+            RestartPolicy::Restart { max_failures } if supervised.consecutive_failures >= max_failures => {
+                ctx.logger().warning(&format!(
+                    "[{}] Restarting worker '{}' after {} consecutive failures",
+                    c, supervised.worker.name(), supervised.consecutive_failures
+                ));
+                supervised.consecutive_failures = 0;
+
+                let scoped = supervised.scoped(ctx);
+                if let Err(e) = supervised.worker.deinitialize(scoped.clone()) {
+                    ctx.logger().error(&format!(
+                        "[{}] Error while deinitializing worker '{}' for restart: {}",
+                        c, supervised.worker.name(), e
+                    ));
+                }
+
+                if let Err(e) = supervised.worker.intialize(scoped) {
+                    ctx.logger().error(&format!(
+                        "[{}] Error while reinitializing worker '{}': {}",
+                        c, supervised.worker.name(), e
+                    ));
+                }
+            }
+            RestartPolicy::Disable { max_failures } if supervised.consecutive_failures >= max_failures => {
+                ctx.logger().error(&format!(
+                    "[{}] Disabling worker '{}' after {} consecutive failures",
+                    c, supervised.worker.name(), supervised.consecutive_failures
+                ));
+                supervised.disabled = true;
+
+                let scoped = supervised.scoped(ctx);
+                if let Err(e) = supervised.worker.deinitialize(scoped) {
+                    ctx.logger().error(&format!(
+                        "[{}] Error while deinitializing disabled worker '{}': {}",
+                        c, supervised.worker.name(), e
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for Application {
+    fn drop(&mut self) {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "drop");
+
+        if self.deinitialized {
+            self.ctx.logger().info(
+                format!("[{}] Application shut down cleanly", c).as_str(),
+            );
+        } else if std::thread::panicking() {
+            self.ctx.logger().error(
+                format!("[{}] Application dropped during a panic without deinitializing", c).as_str(),
+            );
+        } else {
+            self.ctx.logger().warning(
+                format!("[{}] Application dropped without deinitializing", c).as_str(),
+            );
         }
     }
 }
@@ -95,8 +224,9 @@ impl WorkerTrait for Application {
         ctx.logger().info(
             format!("[{}] Initializing application", c).as_str(),
         );
-        for worker in &mut self.workers {
-            match worker.intialize(ctx.clone()) {
+        for supervised in &mut self.workers {
+            let scoped = supervised.scoped(&ctx);
+            match supervised.worker.intialize(scoped) {
                 Ok(_) => {}
                 Err(e) => {
                     ctx.logger().error(&format!(
@@ -123,21 +253,31 @@ impl WorkerTrait for Application {
             for i in 0..self.workers.len() {
                 let iter_start = Instant::now();
 
-                let worker = &mut self.workers[i];
-                match worker.do_work(ctx.clone()) {
-                    Ok(_) => {}
+                let supervised = &mut self.workers[i];
+                if supervised.disabled {
+                    continue;
+                }
+
+                let scoped = supervised.scoped(&ctx);
+                let worker = &mut supervised.worker;
+                let succeeded = match worker.do_work(scoped) {
+                    Ok(_) => true,
                     Err(e) => {
                         ctx.logger().error(&format!(
                             "[{}] Error while executing worker: {}",
                             c, e
                         ));
+                        false
                     }
-                }
+                };
 
                 let elapsed_ms = iter_start.elapsed().as_millis();
-                ctx.logger().trace(
+                ctx.logger().trace_lazy(|| {
                     format!("[{}] Worker '{}' took {} ms to complete tick",
-                        c, worker.name(), elapsed_ms).as_str());
+                        c, worker.name(), elapsed_ms)
+                });
+
+                self.supervise(&ctx, i, succeeded);
 
                 match self.process_events() {
                     Ok(_) => {}
@@ -156,7 +296,7 @@ impl WorkerTrait for Application {
 
                 if loop_time > elapsed_time {
                     let sleep_time = loop_time - elapsed_time;
-                    ctx.logger().trace(&format!(
+                    ctx.logger().trace_lazy(|| format!(
                         "[{}] Idle for {:?} ms",
                         c, sleep_time.as_millis()
                     ));
@@ -177,8 +317,9 @@ impl WorkerTrait for Application {
             format!("[{}] Deinitializing application", c).as_str(),
         );
 
-        for worker in &mut self.workers {
-            match worker.deinitialize(ctx.clone()) {
+        for supervised in self.workers.iter_mut().filter(|w| !w.disabled) {
+            let scoped = supervised.scoped(&ctx);
+            match supervised.worker.deinitialize(scoped) {
                 Ok(_) => {}
                 Err(e) => {
                     ctx.logger().error(&format!(
@@ -192,14 +333,15 @@ impl WorkerTrait for Application {
         ctx.logger().info(
             format!("[{}] Shutting down now", c).as_str(),
         );
+        self.deinitialized = true;
         Ok(())
     }
 
     fn process_events(&mut self) -> Result<()> {
         let c = format!("{}::{}", std::any::type_name::<Self>(), "process_events");
 
-        for worker in &mut self.workers {
-            match worker.process_events() {
+        for supervised in self.workers.iter_mut().filter(|w| !w.disabled) {
+            match supervised.worker.process_events() {
                 Ok(_) => {}
                 Err(e) => {
                     self.ctx.logger().error(&format!(
@@ -222,6 +364,187 @@ impl ApplicationTrait for Application {
     }
 
     fn add_worker(&mut self, worker: Box<dyn WorkerTrait>) {
-        self.workers.push(worker);
+        self.add_worker_with_policy(worker, RestartPolicy::Ignore);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use crate::error::Error;
+    use crate::framework::client::Client;
+    use crate::loggers::common::{LogLevel, LoggerTrait};
+
+    struct Quiet;
+
+    impl LoggerTrait for Quiet {
+        fn log(&self, _level: &LogLevel, _message: &str) {}
+
+        fn level(&self) -> LogLevel {
+            LogLevel::Error
+        }
+    }
+
+    type Calls = Rc<RefCell<Vec<String>>>;
+
+    /// Fails its first `fail_first` ticks and sets the quit flag on its
+    /// `quit_after`th tick, recording every call as "label:method".
+    struct Stub {
+        label: &'static str,
+        fail_first: usize,
+        quit_after: Option<usize>,
+        ticks: usize,
+        calls: Calls,
+    }
+
+    impl Stub {
+        fn new(
+            label: &'static str,
+            fail_first: usize,
+            quit_after: Option<usize>,
+            calls: &Calls,
+        ) -> Box<Self> {
+            Box::new(Stub {
+                label,
+                fail_first,
+                quit_after,
+                ticks: 0,
+                calls: calls.clone(),
+            })
+        }
+
+        fn record(&self, method: &str) {
+            self.calls.borrow_mut().push(format!("{}:{}", self.label, method));
+        }
+    }
+
+    impl WorkerTrait for Stub {
+        fn intialize(&mut self, _ctx: Context) -> Result<()> {
+            self.record("intialize");
+            Ok(())
+        }
+
+        fn do_work(&mut self, ctx: Context) -> Result<()> {
+            self.ticks += 1;
+            if self.quit_after == Some(self.ticks) {
+                ctx.quit().set(true);
+            }
+
+            if self.ticks <= self.fail_first {
+                self.record("do_work:err");
+                return Err(Error::from_client("tick failed"));
+            }
+
+            self.record("do_work:ok");
+            Ok(())
+        }
+
+        fn deinitialize(&mut self, _ctx: Context) -> Result<()> {
+            self.record("deinitialize");
+            Ok(())
+        }
+
+        fn process_events(&mut self) -> Result<()> {
+            self.record("process_events");
+            Ok(())
+        }
+    }
+
+    fn run(workers: Vec<(Box<Stub>, RestartPolicy)>) {
+        let ctx = Context::new(
+            Database::new(Client::new(mock::Client::new())),
+            Logger::new(Quiet),
+        );
+        let mut app = Application::new(ctx, 0);
+        for (worker, policy) in workers {
+            app.add_worker_with_policy(worker, policy);
+        }
+        app.execute();
+    }
+
+    fn calls_of(calls: &Calls, label: &str, skip_events: bool) -> Vec<String> {
+        calls
+            .borrow()
+            .iter()
+            .filter_map(|call| call.strip_prefix(label)?.strip_prefix(':'))
+            .filter(|call| !skip_events || *call != "process_events")
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn restart_reinitializes_after_max_failures_and_resets_the_count() {
+        let calls = Calls::default();
+        run(vec![
+            (Stub::new("flaky", 3, None, &calls), RestartPolicy::Restart { max_failures: 2 }),
+            (Stub::new("clock", 0, Some(5), &calls), RestartPolicy::Ignore),
+        ]);
+
+        // The third failure follows a restart, so it alone doesn't restart again
+        assert_eq!(
+            calls_of(&calls, "flaky", true),
+            vec![
+                "intialize",
+                "do_work:err",
+                "do_work:err",
+                "deinitialize",
+                "intialize",
+                "do_work:err",
+                "do_work:ok",
+                "do_work:ok",
+                "deinitialize",
+            ]
+        );
+    }
+
+    #[test]
+    fn successes_reset_the_failure_count() {
+        let calls = Calls::default();
+        run(vec![
+            (Stub::new("flaky", 1, None, &calls), RestartPolicy::Disable { max_failures: 2 }),
+            (Stub::new("clock", 0, Some(3), &calls), RestartPolicy::Ignore),
+        ]);
+
+        assert_eq!(
+            calls_of(&calls, "flaky", true),
+            vec!["intialize", "do_work:err", "do_work:ok", "do_work:ok", "deinitialize"]
+        );
+    }
+
+    #[test]
+    fn disabled_workers_are_skipped_until_shutdown() {
+        let calls = Calls::default();
+        run(vec![
+            (
+                Stub::new("broken", usize::MAX, None, &calls),
+                RestartPolicy::Disable { max_failures: 2 },
+            ),
+            (Stub::new("clock", 0, Some(4), &calls), RestartPolicy::Ignore),
+        ]);
+
+        // Events are processed after each worker's tick, until it is disabled
+        assert_eq!(
+            calls_of(&calls, "broken", false),
+            vec![
+                "intialize",
+                "do_work:err",
+                "process_events",
+                "process_events",
+                "do_work:err",
+                "deinitialize",
+            ]
+        );
+        assert_eq!(
+            calls_of(&calls, "clock", true),
+            vec![
+                "intialize",
+                "do_work:ok",
+                "do_work:ok",
+                "do_work:ok",
+                "do_work:ok",
+                "deinitialize",
+            ]
+        );
+    }
+}
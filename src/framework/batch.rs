@@ -0,0 +1,59 @@
+use crate::framework::database::Database;
+use crate::Result;
+use crate::schema::field::Field;
+use crate::schema::value::DatabaseValue;
+
+/// Collects the reads and writes of a `Database::batch` scope. Queued reads
+/// go out together in one request the first time a value is needed, and
+/// queued writes go out together when the scope ends.
+pub struct BatchCtx {
+    database: Database,
+    pending_reads: Vec<Field>,
+    pending_writes: Vec<Field>,
+}
+
+impl BatchCtx {
+    pub fn new(database: Database) -> Self {
+        Self {
+            database,
+            pending_reads: vec![],
+            pending_writes: vec![],
+        }
+    }
+
+    /// Queues `field` to be read with the next flush.
+    pub fn read(&mut self, field: &Field) {
+        self.pending_reads.push(field.clone());
+    }
+
+    /// Returns the value of `field`, first reading every queued field in
+    /// one request if any are pending.
+    pub fn value(&mut self, field: &Field) -> Result<DatabaseValue> {
+        self.flush_reads()?;
+        Ok(field.value())
+    }
+
+    pub fn flush_reads(&mut self) -> Result<()> {
+        if self.pending_reads.is_empty() {
+            return Ok(());
+        }
+
+        let requests = std::mem::take(&mut self.pending_reads);
+        self.database.read(&requests)
+    }
+
+    /// Queues `field` to be written with its current value when the scope
+    /// ends.
+    pub fn write(&mut self, field: &Field) {
+        self.pending_writes.push(field.clone());
+    }
+
+    pub fn flush_writes(&mut self) -> Result<()> {
+        if self.pending_writes.is_empty() {
+            return Ok(());
+        }
+
+        let requests = std::mem::take(&mut self.pending_writes);
+        self.database.write(&requests)
+    }
+}
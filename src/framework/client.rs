@@ -1,11 +1,16 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
 
 use crate::clients::common::ClientTrait;
+use crate::framework::logger::Logger;
 use crate::Result;
-use crate::schema::entity::Entity;
+use crate::schema::entity::{Entity, EntityMetadata, EntityPage, EntitySchema};
 use crate::schema::field::Field;
-use crate::schema::notification::{Notification, Config, Token};
+use crate::schema::notification::{Notification, Config, Lease, Token};
+use crate::schema::server::ServerInfo;
 
 type ClientRef = Rc<RefCell<dyn ClientTrait>>;
 pub struct Client(ClientRef);
@@ -15,6 +20,7 @@ impl Client {
         Client(Rc::new(RefCell::new(client)))
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Self {
         Client(self.0.clone())
     }
@@ -31,26 +37,104 @@ impl Client {
         self.0.borrow_mut().disconnect()
     }
 
+    pub fn field_exists(&self, entity_type: &str, field: &str) -> Result<bool> {
+        self.0.borrow_mut().field_exists(entity_type, field)
+    }
+
     pub fn get_entities(&self, entity_type: &str) -> Result<Vec<Entity>> {
         self.0.borrow_mut().get_entities(entity_type)
     }
 
+    pub fn get_entities_page(
+        &self,
+        entity_type: &str,
+        page_size: usize,
+        cursor: Option<&str>,
+    ) -> Result<EntityPage> {
+        self.0
+            .borrow_mut()
+            .get_entities_page(entity_type, page_size, cursor)
+    }
+
+    pub fn get_entities_sorted(
+        &self,
+        entity_type: &str,
+        sort_field: &str,
+        ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        self.0
+            .borrow_mut()
+            .get_entities_sorted(entity_type, sort_field, ascending)
+    }
+
     pub fn get_entity(&self, entity_id: &str) -> Result<Entity> {
         self.0.borrow_mut().get_entity(entity_id)
     }
 
+    pub fn get_entity_metadata(&self, entity_id: &str) -> Result<EntityMetadata> {
+        self.0.borrow_mut().get_entity_metadata(entity_id)
+    }
+
+    pub fn get_entity_schema(&self, entity_type: &str) -> Result<EntitySchema> {
+        self.0.borrow_mut().get_entity_schema(entity_type)
+    }
+
+    pub fn is_field_writable(&self, entity_type: &str, field: &str) -> Result<bool> {
+        self.0.borrow_mut().is_field_writable(entity_type, field)
+    }
+
     pub fn get_notifications(&self) -> Result<Vec<Notification>> {
         self.0.borrow_mut().get_notifications()
     }
 
+    pub fn get_notifications_longpoll(&self, wait: Duration) -> Result<Vec<Notification>> {
+        self.0.borrow_mut().get_notifications_longpoll(wait)
+    }
+
+    pub fn notification_backlog(&self) -> Result<usize> {
+        self.0.borrow_mut().notification_backlog()
+    }
+
     pub fn read(&self, requests: &Vec<Field>) -> Result<()> {
         self.0.borrow_mut().read(requests)
     }
 
+    pub fn read_changed_since(
+        &self,
+        requests: &Vec<Field>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        self.0.borrow_mut().read_changed_since(requests, since)
+    }
+
+    pub fn read_with_previous(&self, requests: &Vec<Field>) -> Result<Vec<Option<Field>>> {
+        self.0.borrow_mut().read_with_previous(requests)
+    }
+
     pub fn register_notification(&self, config: &Config) -> Result<Token> {
         self.0.borrow_mut().register_notification(config)
     }
 
+    pub fn register_notification_leased(&self, config: &Config) -> Result<Lease> {
+        self.0.borrow_mut().register_notification_leased(config)
+    }
+
+    pub fn server_info(&self) -> Result<ServerInfo> {
+        self.0.borrow_mut().server_info()
+    }
+
+    pub fn set_logger(&self, logger: Logger) {
+        self.0.borrow_mut().set_logger(logger)
+    }
+
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        self.0.borrow_mut().set_timeout(timeout)
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.0.borrow().timeout()
+    }
+
     pub fn unregister_notification(&self, token: &Token) -> Result<()> {
         self.0.borrow_mut().unregister_notification(token)
     }
@@ -58,4 +142,8 @@ impl Client {
     pub fn write(&self, requests: &Vec<Field>) -> Result<()> {
         self.0.borrow_mut().write(requests)
     }
+
+    pub fn write_at(&self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        self.0.borrow_mut().write_at(requests, write_time)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,159 @@
+use crate::error::Error;
+use crate::framework::database::Database;
+use crate::Result;
+use crate::schema::field::{Field, RawField};
+use crate::schema::value::RawValue;
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Reads an application's configuration stored as fields of one entity.
+/// Values are cached after the first read; call `invalidate` once per tick
+/// to pick up changes while keeping repeated lookups within a tick free.
+pub struct ConfigReader {
+    database: Database,
+    entity_id: String,
+    cache: HashMap<String, RawValue>,
+}
+
+impl ConfigReader {
+    pub fn new(database: Database, entity_id: &str) -> Self {
+        Self {
+            database,
+            entity_id: entity_id.to_string(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn entity_id(&self) -> String {
+        self.entity_id.clone()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Reads every key not cached yet in one request.
+    pub fn prefetch(&mut self, keys: &[&str]) -> Result<()> {
+        let requests: Vec<Field> = keys
+            .iter()
+            .filter(|key| !self.cache.contains_key(**key))
+            .map(|key| RawField::new(self.entity_id.clone(), *key).into_field())
+            .collect();
+
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        self.database.read(&requests)?;
+
+        for field in requests {
+            self.cache.insert(field.name(), field.value().into_raw());
+        }
+
+        Ok(())
+    }
+
+    pub fn get_raw(&mut self, key: &str) -> Result<RawValue> {
+        if let Some(value) = self.cache.get(key) {
+            return Ok(value.clone());
+        }
+
+        let field = RawField::new(self.entity_id.clone(), key).into_field();
+        self.database.read(&vec![field.clone()])?;
+
+        let value = field.value().into_raw();
+        self.cache.insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    pub fn get_i64(&mut self, key: &str) -> Result<i64> {
+        self.get_typed(key, "an integer", RawValue::as_opt_i64)?
+            .ok_or_else(|| self.missing(key))
+    }
+
+    pub fn get_f64(&mut self, key: &str) -> Result<f64> {
+        self.get_typed(key, "a float", RawValue::as_opt_f64)?
+            .ok_or_else(|| self.missing(key))
+    }
+
+    pub fn get_bool(&mut self, key: &str) -> Result<bool> {
+        self.get_typed(key, "a boolean", RawValue::as_opt_bool)?
+            .ok_or_else(|| self.missing(key))
+    }
+
+    pub fn get_str(&mut self, key: &str) -> Result<String> {
+        self.get_typed(key, "a string", RawValue::as_opt_str)?
+            .ok_or_else(|| self.missing(key))
+    }
+
+    /// Like `get_i64`, but returns `default` when the value is unset.
+    pub fn get_i64_or(&mut self, key: &str, default: i64) -> Result<i64> {
+        Ok(self
+            .get_typed(key, "an integer", RawValue::as_opt_i64)?
+            .unwrap_or(default))
+    }
+
+    pub fn get_f64_or(&mut self, key: &str, default: f64) -> Result<f64> {
+        Ok(self
+            .get_typed(key, "a float", RawValue::as_opt_f64)?
+            .unwrap_or(default))
+    }
+
+    pub fn get_bool_or(&mut self, key: &str, default: bool) -> Result<bool> {
+        Ok(self
+            .get_typed(key, "a boolean", RawValue::as_opt_bool)?
+            .unwrap_or(default))
+    }
+
+    pub fn get_str_or(&mut self, key: &str, default: &str) -> Result<String> {
+        Ok(self
+            .get_typed(key, "a string", RawValue::as_opt_str)?
+            .unwrap_or_else(|| default.to_string()))
+    }
+
+    /// Like `get_i64_or`, but also fails unless the value lies in `range`.
+    pub fn get_i64_in(&mut self, key: &str, default: i64, range: RangeInclusive<i64>) -> Result<i64> {
+        let value = self.get_i64_or(key, default)?;
+        if !range.contains(&value) {
+            return Err(self.invalid(key, &format!("{} is outside {:?}", value, range)));
+        }
+
+        Ok(value)
+    }
+
+    pub fn get_f64_in(&mut self, key: &str, default: f64, range: RangeInclusive<f64>) -> Result<f64> {
+        let value = self.get_f64_or(key, default)?;
+        if !range.contains(&value) {
+            return Err(self.invalid(key, &format!("{} is outside {:?}", value, range)));
+        }
+
+        Ok(value)
+    }
+
+    fn get_typed<T>(
+        &mut self,
+        key: &str,
+        expected: &str,
+        convert: fn(&RawValue) -> Result<Option<T>>,
+    ) -> Result<Option<T>> {
+        let value = self.get_raw(key)?;
+        convert(&value).map_err(|_| {
+            self.invalid(key, &format!("expected {}, found {:?}", expected, value.kind()))
+        })
+    }
+
+    fn missing(&self, key: &str) -> Box<dyn std::error::Error> {
+        Error::from_database_field(&format!(
+            "Config key '{}' of entity '{}' is not set",
+            key, self.entity_id
+        ))
+    }
+
+    fn invalid(&self, key: &str, reason: &str) -> Box<dyn std::error::Error> {
+        Error::from_database_field(&format!(
+            "Config key '{}' of entity '{}' is invalid: {}",
+            key, self.entity_id, reason
+        ))
+    }
+}
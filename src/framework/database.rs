@@ -1,21 +1,227 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
+
+use crate::error::{Error, ResultExt};
+use crate::framework::batch::BatchCtx;
 use crate::framework::client::Client;
-use crate::framework::notification::NotificationManager;
+use crate::framework::events::bounded::{BoundedReceiver, FullChannelPolicy};
+use crate::framework::logger::Logger;
+use crate::framework::notification::{NotificationManager, NotificationSink, NotificationStats, SinkHandle};
 use crate::Result;
 use crate::schema::field::{Field, RawField};
 use crate::schema::notification::{Notification, Config, Token};
-use crate::schema::entity::Entity;
+use crate::schema::entity::{Entity, EntityMetadata, EntityPage, EntitySchema, FieldSchema};
+use crate::schema::server::ServerInfo;
+use crate::schema::snapshot::Snapshot;
+use crate::schema::value::{RawValue, ValueKind};
+
+const INCREMENT_MAX_ATTEMPTS: usize = 5;
+const ENTITY_PAGE_SIZE: usize = 500;
+const SYSTEM_WRITER_NAME: &str = "system";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Sum,
+    Min,
+    Max,
+    Avg,
+}
+
+fn describe_fields(fields: &[Field]) -> String {
+    match fields {
+        [field] => format!("field '{}' of entity '{}'", field.name(), field.entity_id()),
+        [first, ..] => format!(
+            "{} fields, starting with field '{}' of entity '{}'",
+            fields.len(),
+            first.name(),
+            first.entity_id()
+        ),
+        [] => "no fields".to_string(),
+    }
+}
+
+/// What `Database::write` does with fields the schema marks read-only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnlyFieldPolicy {
+    /// Send them anyway and let the server decide.
+    Send,
+    /// Leave them out of the write, logging a warning.
+    Skip,
+    /// Fail the whole write without sending anything.
+    Fail,
+}
+
+/// Maps a schema field type such as "qdb.Float" to the value kind it holds.
+fn kind_of_type_name(type_name: &str) -> Option<ValueKind> {
+    match type_name.rsplit('.').next()? {
+        "String" => Some(ValueKind::String),
+        "Int" => Some(ValueKind::Integer),
+        "Float" => Some(ValueKind::Float),
+        "Bool" => Some(ValueKind::Boolean),
+        "EntityReference" => Some(ValueKind::EntityReference),
+        "Timestamp" => Some(ValueKind::Timestamp),
+        "ConnectionState" => Some(ValueKind::ConnectionState),
+        "GarageDoorState" => Some(ValueKind::GarageDoorState),
+        "Blob" => Some(ValueKind::Blob),
+        _ => None,
+    }
+}
+
+/// Orders values for client-side sorting: numbers numerically across
+/// integers and floats, strings and timestamps naturally, and unspecified
+/// values or values of differing kinds after the rest.
+fn compare_for_sort(a: &RawValue, b: &RawValue) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let number = |v: &RawValue| match v {
+        RawValue::Integer(i) => Some(*i as f64),
+        RawValue::Float(f) => Some(*f),
+        _ => None,
+    };
+
+    if let (Some(a), Some(b)) = (number(a), number(b)) {
+        return a.total_cmp(&b);
+    }
+
+    match (a, b) {
+        (RawValue::Boolean(a), RawValue::Boolean(b)) => a.cmp(b),
+        (RawValue::Timestamp(a), RawValue::Timestamp(b)) => a.cmp(b),
+        (RawValue::Blob(a), RawValue::Blob(b)) => a.cmp(b),
+        (RawValue::Unspecified, RawValue::Unspecified) => Ordering::Equal,
+        (RawValue::Unspecified, _) => Ordering::Greater,
+        (_, RawValue::Unspecified) => Ordering::Less,
+        _ if a.kind() == b.kind() => a.to_display_string().cmp(&b.to_display_string()),
+        _ => format!("{:?}", a.kind()).cmp(&format!("{:?}", b.kind())),
+    }
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` its output is fixed, so
+/// fingerprints built with it stay comparable across Rust releases.
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new() -> Self {
+        Fingerprint(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Writes the length first, so adjacent strings can't run together.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn write_time(&mut self, t: &DateTime<Utc>) {
+        self.write(&t.timestamp().to_le_bytes());
+        self.write(&t.timestamp_subsec_nanos().to_le_bytes());
+    }
+
+    /// Writes a tag byte for the kind, which never changes for a kind,
+    /// followed by the value.
+    fn write_value(&mut self, value: &RawValue) {
+        match value {
+            RawValue::Unspecified => self.write(&[0]),
+            RawValue::String(s) => {
+                self.write(&[1]);
+                self.write_bytes(s.as_bytes());
+            }
+            RawValue::Integer(i) => {
+                self.write(&[2]);
+                self.write(&i.to_le_bytes());
+            }
+            RawValue::Float(f) => {
+                // -0.0 == 0.0, so both must fingerprint alike
+                self.write(&[3]);
+                self.write(&(if *f == 0.0 { 0.0f64 } else { *f }).to_bits().to_le_bytes());
+            }
+            RawValue::Boolean(b) => self.write(&[4, *b as u8]),
+            RawValue::EntityReference(e) => {
+                self.write(&[5]);
+                self.write_bytes(e.as_bytes());
+            }
+            RawValue::Timestamp(t) => {
+                self.write(&[6]);
+                self.write_time(t);
+            }
+            RawValue::ConnectionState(c) => {
+                self.write(&[7]);
+                self.write_bytes(c.as_bytes());
+            }
+            RawValue::GarageDoorState(g) => {
+                self.write(&[8]);
+                self.write_bytes(g.as_bytes());
+            }
+            RawValue::Blob(b) => {
+                self.write(&[9]);
+                self.write_bytes(b);
+            }
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Fingerprints the names, values and write times of `fields`, in order.
+fn fingerprint_fields(fields: &[Field]) -> u64 {
+    let mut fingerprint = Fingerprint::new();
+    for field in fields {
+        fingerprint.write_bytes(field.name().as_bytes());
+        fingerprint.write_value(&field.value().into_raw());
+        fingerprint.write_time(&field.write_time());
+    }
+
+    fingerprint.finish()
+}
+
+/// Notifications from `register_notifications_multiplexed`, each paired
+/// with the config it was registered under.
+pub type MultiplexedReceiver = Receiver<(Config, Notification)>;
+
+/// Tags notifications with their config for
+/// `register_notifications_multiplexed`.
+struct Multiplexed {
+    config: Config,
+    sender: Sender<(Config, Notification)>,
+}
+
+impl NotificationSink for Multiplexed {
+    fn deliver(&mut self, notification: &Notification) {
+        // A dropped receiver only means nobody is listening anymore
+        let _ = self.sender.send((self.config.clone(), notification.clone()));
+    }
+}
 
 pub struct _Database {
     client: Client,
     notification_manager: NotificationManager,
+    writer_names: RefCell<HashMap<String, String>>,
+    validate_writes: bool,
+    auto_coerce_writes: bool,
+    entity_types: RefCell<HashMap<String, String>>,
+    entity_schemas: RefCell<HashMap<String, EntitySchema>>,
+    read_only_policy: ReadOnlyFieldPolicy,
+    logged_fallbacks: RefCell<HashSet<&'static str>>,
+    logger: Option<Logger>,
 }
 
 type DatabaseRef = Rc<RefCell<_Database>>;
+
+/// Reads, writes and notifications through a `Client`. It does nothing on
+/// its own between calls, so periodic reads of a field are done by
+/// `workers::poll::Worker::poll_field` from an `Application`'s loop.
 pub struct Database(DatabaseRef);
 
 impl Database {
@@ -23,10 +229,15 @@ impl Database {
         Database(Rc::new(RefCell::new(_Database::new(client))))
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Self {
         Database(self.0.clone())
     }
 
+    pub fn set_logger(&self, logger: Logger) {
+        self.0.borrow_mut().set_logger(logger)
+    }
+
     pub fn connect(&self) -> Result<()> {
         self.0.borrow().connect()
     }
@@ -39,35 +250,322 @@ impl Database {
         self.0.borrow().disconnect()
     }
 
+    pub fn get_entity_schema(&self, entity_type: &str) -> Result<EntitySchema> {
+        self.0.borrow().get_entity_schema(entity_type)
+    }
+
+    /// Checks the server's schema for `field` on `entity_type`. An unknown
+    /// field yields `Ok(false)`; failing to reach the server is an error.
+    pub fn field_exists(&self, entity_type: &str, field: &str) -> Result<bool> {
+        self.0.borrow().field_exists(entity_type, field)
+    }
+
+    /// Checks the server's schema for whether `field` on `entity_type`
+    /// accepts writes. Unlike `field_exists`, an unknown field is an error.
+    pub fn is_field_writable(&self, entity_type: &str, field: &str) -> Result<bool> {
+        self.0.borrow().is_field_writable(entity_type, field)
+    }
+
+    /// Returns the entities of `entity_type` whose `fields` satisfy `predicate`.
+    /// Unless `strict` is set, fields that cannot be read are passed to the
+    /// predicate as `Unspecified` instead of failing the whole search.
     pub fn find(
         &self,
         entity_type: &str,
         field: &Vec<String>,
         predicate: fn(&HashMap<String, Field>) -> bool,
+        strict: bool,
     ) -> Result<Vec<Entity>> {
-        self.0.borrow().find(entity_type, field, predicate)
+        self.0.borrow().find(entity_type, field, predicate, strict)
+    }
+
+    /// Queries the server's version, uptime and entity counts. Fails as
+    /// unsupported when the client has no status endpoint.
+    pub fn server_info(&self) -> Result<ServerInfo> {
+        self.0.borrow().server_info()
     }
 
     pub fn get_entity(&self, entity_id: &str) -> Result<Entity> {
         self.0.borrow().get_entity(entity_id)
     }
 
+    /// Fetches creation and modification details of `entity_id`, for audit
+    /// tooling. Details the server does not report are `None`.
+    pub fn get_entity_metadata(&self, entity_id: &str) -> Result<EntityMetadata> {
+        self.0.borrow().get_entity_metadata(entity_id)
+    }
+
     pub fn get_entities(&self, entity_type: &str) -> Result<Vec<Entity>> {
         self.0.borrow().get_entities(entity_type)
     }
 
+    /// Calls `f` for each entity of `entity_type`, fetching them a page at a
+    /// time so the full list is never held in memory. Clients without paging
+    /// fall back to `get_entities`. Stops at the first error from `f`. The
+    /// database isn't borrowed while `f` runs, so `f` may use it.
+    pub fn for_each_entity(
+        &self,
+        entity_type: &str,
+        mut f: impl FnMut(Entity) -> Result<()>,
+    ) -> Result<()> {
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page = self
+                .0
+                .borrow()
+                .get_entities_page(entity_type, cursor.as_deref());
+
+            let page = match page {
+                Err(e) if Error::is_unsupported(e.as_ref()) => {
+                    return self.get_entities(entity_type)?.into_iter().try_for_each(f);
+                }
+                result => result?,
+            };
+
+            page.entities.into_iter().try_for_each(&mut f)?;
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Resolves the entities referenced by `children_field` of `entity_id`.
+    /// Until list values are supported, the field may hold a single entity
+    /// reference or a comma-separated string of entity ids.
+    pub fn children(&self, entity_id: &str, children_field: &str) -> Result<Vec<Entity>> {
+        self.0.borrow().children(entity_id, children_field)
+    }
+
+    pub fn parent(&self, entity_id: &str, parent_field: &str) -> Result<Option<Entity>> {
+        self.0.borrow().parent(entity_id, parent_field)
+    }
+
+    /// Returns the entities of `entity_type` ordered by `sort_field`, ties
+    /// broken by name. The ordering is requested from the server; if the
+    /// client does not support it, `sort_field` is read for every entity and
+    /// they are sorted locally, with unset values last. The REST client has
+    /// no sorting endpoint, so with it sorting always happens locally.
+    pub fn get_entities_sorted(
+        &self,
+        entity_type: &str,
+        sort_field: &str,
+        ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        self.0
+            .borrow()
+            .get_entities_sorted(entity_type, sort_field, ascending)
+    }
+
     pub fn read(&self, requests: &Vec<Field>) -> Result<()> {
         self.0.borrow().read(requests)
     }
 
+    /// Returns the fields of `requests` written after `since`, with their
+    /// values read. The filter is passed on to the server when the client
+    /// supports it; otherwise every field is read and the unchanged ones are
+    /// dropped locally, which saves processing but not bandwidth. The REST
+    /// client doesn't support it, so with it the filtering is always local.
+    pub fn read_changed_since(
+        &self,
+        requests: &Vec<Field>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        self.0.borrow().read_changed_since(requests, since)
+    }
+
+    /// Reads `requests` and pairs each field with its value prior to the
+    /// latest write. The previous value is `None` when the client or server
+    /// doesn't keep it, in which case this is a plain `read`; that is always
+    /// the case with the REST client.
+    pub fn read_with_previous(&self, requests: &Vec<Field>) -> Result<Vec<(Field, Option<Field>)>> {
+        self.0.borrow().read_with_previous(requests)
+    }
+
+    /// Reads like `read`, but bounds the request by `deadline`. This is
+    /// timeout-based: the remaining time is handed to the client as its
+    /// read timeout, so the request is abandoned by the transport rather
+    /// than cancelled asynchronously. With clients that ignore timeouts the
+    /// deadline is only checked once the read returns.
+    pub fn read_with_deadline(&self, requests: &Vec<Field>, deadline: Instant) -> Result<()> {
+        self.0.borrow().read_with_deadline(requests, deadline)
+    }
+
+    pub fn read_matrix(
+        &self,
+        entity_ids: &[String],
+        fields: &[String],
+    ) -> Result<HashMap<String, HashMap<String, Field>>> {
+        self.0.borrow().read_matrix(entity_ids, fields)
+    }
+
+    /// Reads `fields` of `entity_id` in one batch into a map of field name
+    /// to value. Fields without a value map to `RawValue::Unspecified`.
+    pub fn read_entity_values(
+        &self,
+        entity_id: &str,
+        fields: &[String],
+    ) -> Result<HashMap<String, RawValue>> {
+        self.0.borrow().read_entity_values(entity_id, fields)
+    }
+
+    /// Reads `field` of every entity of `entity_type` in one batch and folds
+    /// the integer values with `aggregation`, skipping unset fields. `Avg`
+    /// rounds toward zero. Only `Sum` is defined when no values are set.
+    pub fn aggregate_i64(&self, entity_type: &str, field: &str, aggregation: Aggregation) -> Result<i64> {
+        self.0.borrow().aggregate_i64(entity_type, field, aggregation)
+    }
+
+    /// Like `aggregate_i64` for float values. Integer values are included
+    /// as floats.
+    pub fn aggregate_f64(&self, entity_type: &str, field: &str, aggregation: Aggregation) -> Result<f64> {
+        self.0.borrow().aggregate_f64(entity_type, field, aggregation)
+    }
+
+    /// Reads `fields` of every entity in one batch and captures the values
+    /// with their write times and writers, e.g. for backups or audits.
+    pub fn snapshot(&self, entity_ids: &[String], fields: &[String]) -> Result<Snapshot> {
+        self.0.borrow().snapshot(entity_ids, fields)
+    }
+
+    /// Reads `fields` of `entity_id` and hashes their names, values and write
+    /// times into one value, independent of the order of `fields`. Equal
+    /// fingerprints mean nothing changed between reads. The hash is a fixed
+    /// algorithm, so fingerprints can be stored and compared across builds.
+    pub fn entity_fingerprint(&self, entity_id: &str, fields: &[String]) -> Result<u64> {
+        self.0.borrow().entity_fingerprint(entity_id, fields)
+    }
+
+    pub fn read_expecting(&self, requests: &[(Field, ValueKind)]) -> Result<()> {
+        self.0.borrow().read_expecting(requests)
+    }
+
     pub fn write(&self, requests: &Vec<Field>) -> Result<()> {
         self.0.borrow().write(requests)
     }
 
+    /// Runs `f` with a `BatchCtx` that groups its reads and writes into as
+    /// few requests as possible. Queued writes are sent once `f` returns
+    /// successfully and dropped if it fails. This is not a transaction: the
+    /// server applies the writes without checking that the values read are
+    /// still current, and other writers may interleave.
+    pub fn batch(&self, f: impl FnOnce(&mut BatchCtx) -> Result<()>) -> Result<()> {
+        let mut ctx = BatchCtx::new(self.clone());
+        f(&mut ctx)?;
+        ctx.flush_writes()
+    }
+
+    /// Stamps every field with `write_time` and writes them so the server
+    /// records one coherent timestamp for the whole batch.
+    pub fn write_at(&self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        self.0.borrow().write_at(requests, write_time)
+    }
+
+    /// Reads the field and writes its current value back with the write time
+    /// set to now, as for a heartbeat. This still issues a real write, and
+    /// relies on the client forwarding the write time.
+    pub fn touch_field(&self, entity_id: &str, field: &str) -> Result<()> {
+        self.0.borrow().touch_field(entity_id, field)
+    }
+
+    /// Controls the check, enabled by default, that rejects a write batch
+    /// targeting the same entity field twice with different values.
+    /// Identical duplicates are dropped either way while it is enabled.
+    pub fn set_validate_writes(&self, validate: bool) {
+        self.0.borrow_mut().validate_writes = validate;
+    }
+
+    /// When enabled, values written are first converted to the type the
+    /// entity's schema declares for the field, where that loses nothing
+    /// (see `RawValue::coerce_to`), and the write fails if they can't be.
+    /// The written `Field`s are updated with the coerced values. Entity
+    /// types and schemas are looked up once and cached. Off by default.
+    pub fn set_auto_coerce_writes(&self, coerce: bool) {
+        self.0.borrow_mut().auto_coerce_writes = coerce;
+    }
+
+    /// Controls writes to fields the entity schema marks read-only, such as
+    /// fields the server derives. Any policy but the default `Send` looks up
+    /// and caches schemas the same way `set_auto_coerce_writes` does.
+    pub fn set_read_only_field_policy(&self, policy: ReadOnlyFieldPolicy) {
+        self.0.borrow_mut().read_only_policy = policy;
+    }
+
+    /// Reads both fields and writes each the other's value in one batch.
+    /// The server applies the two writes separately, so this is not atomic,
+    /// but batching keeps the window small. Fails without writing if the
+    /// values are of different kinds.
+    pub fn swap_fields(&self, a: (&str, &str), b: (&str, &str)) -> Result<()> {
+        self.0.borrow().swap_fields(a, b)
+    }
+
+    /// Writes `field`, then reads it back and fails with
+    /// `Error::VerificationFailed` if the server holds a different value.
+    /// Integer and float values that compare equal numerically are accepted,
+    /// since the server may coerce between them. This costs an extra round
+    /// trip and is only worth it for writes that must not be lost silently.
+    pub fn write_and_verify(&self, field: Field) -> Result<()> {
+        self.0.borrow().write_and_verify(field)
+    }
+
+    /// Writes `field` only if the server still holds `expected`, returning
+    /// whether the write was issued. The comparison is made client-side with
+    /// a fresh read, so it narrows the race window but does not close it.
+    pub fn write_if_unchanged(&self, field: &Field, expected: &RawValue) -> Result<bool> {
+        self.0.borrow().write_if_unchanged(field, expected)
+    }
+
+    /// Reads the server's value of `field` into a separate request and
+    /// returns whether it equals the local value. `field` is left untouched,
+    /// and write time and writer are not compared.
+    pub fn is_in_sync(&self, field: &Field) -> Result<bool> {
+        self.0.borrow().is_in_sync(field)
+    }
+
+    /// Adds `delta` to an integer field and returns the new value. An unset
+    /// field counts as zero. The update goes through `write_if_unchanged` and
+    /// is retried on conflict, up to a fixed number of attempts.
+    pub fn increment(&self, entity_id: &str, field: &str, delta: i64) -> Result<i64> {
+        self.0.borrow().increment(entity_id, field, delta)
+    }
+
+    /// Writes `default` to the field only if it is currently unspecified,
+    /// returning whether it wrote. Goes through `write_if_unchanged`, so it
+    /// is safe to run repeatedly during provisioning.
+    pub fn ensure_field(&self, entity_id: &str, field: &str, default: RawValue) -> Result<bool> {
+        self.0.borrow().ensure_field(entity_id, field, default)
+    }
+
+    /// Resolves the name of the entity that last wrote `field`. Lookups are
+    /// cached for the lifetime of the database. Fields without a writer are
+    /// reported as written by "system".
+    pub fn writer_name(&self, field: &Field) -> Result<String> {
+        self.0.borrow().writer_name(field)
+    }
+
     pub fn clear_notifications(&self) {
         self.0.borrow().clear_notifications();
     }
 
+    /// Drops every notification sender, so consumers blocked on `recv()`
+    /// see a disconnection. Meant for shutdown; see
+    /// `NotificationManager::close_all`.
+    pub fn close_notifications(&self) {
+        self.0.borrow().close_notifications();
+    }
+
+    /// Opts into keeping notification registrations across `clear_notifications`
+    /// so that `reregister_notifications` can restore them after a reconnect.
+    pub fn set_preserve_notifications(&self, preserve: bool) {
+        self.0.borrow().set_preserve_notifications(preserve);
+    }
+
+    pub fn reregister_notifications(&self) -> Result<usize> {
+        self.0.borrow().reregister_notifications()
+    }
+
     pub fn register_notification(
         &self,
         config: &Config,
@@ -75,13 +573,193 @@ impl Database {
         self.0.borrow().register_notification(config)
     }
 
+    /// Registers like `register_notification`, but the receiver holds at
+    /// most `capacity` notifications. What happens beyond that is set with
+    /// `set_notification_full_channel_policy`.
+    pub fn register_notification_bounded(
+        &self,
+        config: &Config,
+        capacity: usize,
+    ) -> Result<BoundedReceiver<Notification>> {
+        self.0.borrow().register_notification_bounded(config, capacity)
+    }
+
+    /// Registers for changes to `field` on every entity of `entity_type`. Use
+    /// the demux worker's `by_entity` to route the stream per entity.
+    pub fn register_type_notification(
+        &self,
+        entity_type: &str,
+        field: &str,
+    ) -> Result<Receiver<Notification>> {
+        self.register_notification(&Config {
+            entity_id: String::new(),
+            entity_type: entity_type.into(),
+            field: field.into(),
+            notify_on_change: true,
+            context: vec![],
+        })
+    }
+
+    /// Builds a notification config for `field` of the entity of
+    /// `entity_type` named `name`. Fails unless exactly one entity of the
+    /// type has that name.
+    pub fn notification_for_named(
+        &self,
+        entity_type: &str,
+        name: &str,
+        field: &str,
+        notify_on_change: bool,
+    ) -> Result<Config> {
+        self.0
+            .borrow()
+            .notification_for_named(entity_type, name, field, notify_on_change)
+    }
+
+    /// Checks that the entity, entity type, notifying field and context
+    /// fields of `config` exist, failing with an error naming the first one
+    /// that doesn't. Field checks are skipped when the client has no schema
+    /// support.
+    pub fn validate_notification_config(&self, config: &Config) -> Result<()> {
+        self.0.borrow().validate_notification_config(config)
+    }
+
+    /// Registers like `register_notification`, but only delivers notifications
+    /// for which `filter` returns true. `context` names the context fields
+    /// the filter inspects; they are added to the registered config, so the
+    /// registration belongs to `config.with_context(context)`, which is also
+    /// what to unregister.
+    pub fn register_filtered(
+        &self,
+        config: &Config,
+        context: &[&str],
+        filter: fn(&Notification) -> bool,
+    ) -> Result<Receiver<Notification>> {
+        self.0.borrow().register_filtered(config, context, filter)
+    }
+
+    /// Registers every config in `configs` and funnels their notifications
+    /// into one receiver, each paired with the config it was registered
+    /// under. Returns the receiver with one sink handle per config, which
+    /// `unregister_notification_sink` takes to stop them; dropping the
+    /// receiver doesn't. If a registration fails, the ones before it are
+    /// unregistered again.
+    pub fn register_notifications_multiplexed(
+        &self,
+        configs: &[Config],
+    ) -> Result<(MultiplexedReceiver, Vec<SinkHandle>)> {
+        self.0.borrow().register_notifications_multiplexed(configs)
+    }
+
+    /// Registers like `register_notification`, but hands notifications to
+    /// `sink` instead of a channel. The returned handle disconnects the sink
+    /// again through `unregister_notification_sink`.
+    pub fn register_notification_sink(
+        &self,
+        config: &Config,
+        sink: Box<dyn NotificationSink>,
+    ) -> Result<SinkHandle> {
+        self.0.borrow().register_notification_sink(config, sink)
+    }
+
+    /// Disconnects the sink behind `handle`. The registration itself is
+    /// unregistered once nothing else receives from it.
+    pub fn unregister_notification_sink(&self, handle: &SinkHandle) -> Result<()> {
+        self.0.borrow().unregister_notification_sink(handle)
+    }
+
     pub fn unregister_notification(&self, token: &Token) -> Result<()> {
         self.0.borrow().unregister_notification(token)
     }
 
+    /// Synthesizes notifications for changes to the fields of `configs`
+    /// written after `since`, e.g. to reconcile after a disconnect. Only the
+    /// latest write of each field is seen, so intermediate changes are lost,
+    /// and `previous` is unspecified unless the client reports prior values.
+    /// Notifications carry the token of the matching registration, if any.
+    /// This is a best-effort catch-up, not guaranteed delivery.
+    pub fn catch_up(&self, configs: &[Config], since: DateTime<Utc>) -> Result<Vec<Notification>> {
+        self.0.borrow().catch_up(configs, since)
+    }
+
+    pub fn export_notification_configs(&self) -> Vec<Config> {
+        self.0.borrow().export_notification_configs()
+    }
+
+    /// Registers each of `configs`, typically ones previously exported, and
+    /// returns their receivers in the same order. Intended to be called once
+    /// the connection has been (re-)established.
+    pub fn restore_notifications(
+        &self,
+        configs: &[Config],
+    ) -> Result<Vec<Receiver<Notification>>> {
+        self.0.borrow().restore_notifications(configs)
+    }
+
+    /// Unregisters every notification registered for `entity_type`. See
+    /// `NotificationManager::unregister_by_type` for partial failures.
+    pub fn unregister_notifications_by_type(&self, entity_type: &str) -> Result<usize> {
+        self.0.borrow().unregister_notifications_by_type(entity_type)
+    }
+
+    /// Renews every registration whose lease expires within `within`. See
+    /// `NotificationManager::renew_expiring` for partial failures.
+    pub fn renew_expiring_notifications(&self, within: Duration) -> Result<usize> {
+        self.0.borrow().renew_expiring_notifications(within)
+    }
+
+    /// See `NotificationManager::set_dead_consumer_timeout`.
+    pub fn set_dead_consumer_timeout(&self, timeout: Option<Duration>) {
+        self.0.borrow().set_dead_consumer_timeout(timeout)
+    }
+
+    /// See `NotificationManager::set_full_channel_policy`.
+    pub fn set_notification_full_channel_policy(&self, policy: FullChannelPolicy) -> Result<()> {
+        self.0.borrow().set_notification_full_channel_policy(policy)
+    }
+
+    pub fn set_notification_fetch_timeout(&self, timeout: Option<Duration>) {
+        self.0.borrow().set_notification_fetch_timeout(timeout)
+    }
+
+    pub fn set_suppress_loosely_unchanged_notifications(&self, suppress: bool) {
+        self.0.borrow().set_suppress_loosely_unchanged_notifications(suppress)
+    }
+
+    pub fn set_drop_stale_notifications(&self, drop_stale: bool) {
+        self.0.borrow().set_drop_stale_notifications(drop_stale)
+    }
+
+    pub fn set_notification_longpoll_wait(&self, wait: Option<Duration>) {
+        self.0.borrow().set_notification_longpoll_wait(wait)
+    }
+
+    pub fn subscribe_all_notifications(&self) -> Receiver<Notification> {
+        self.0.borrow().subscribe_all_notifications()
+    }
+
+    pub fn notification_stats(&self) -> NotificationStats {
+        self.0.borrow().notification_stats()
+    }
+
+    pub fn reset_notification_stats(&self) {
+        self.0.borrow().reset_notification_stats()
+    }
+
+    /// Returns how many notifications the server has queued for this client.
+    pub fn notification_backlog(&self) -> Result<usize> {
+        self.0.borrow().notification_backlog()
+    }
+
     pub fn process_notifications(&self) -> Result<()> {
         self.0.borrow().process_notifications()
     }
+
+    /// Delivers any notifications still queued on the server, giving up
+    /// after `timeout`. Meant to run once before `close_notifications` at
+    /// shutdown, so consumers receive everything pending.
+    pub fn drain_notifications(&self, timeout: Duration) -> Result<()> {
+        self.0.borrow().drain_notifications(timeout)
+    }
 }
 
 impl _Database {
@@ -89,17 +767,43 @@ impl _Database {
         _Database {
             client,
             notification_manager: NotificationManager::new(),
+            writer_names: RefCell::new(HashMap::new()),
+            validate_writes: true,
+            auto_coerce_writes: false,
+            entity_types: RefCell::new(HashMap::new()),
+            entity_schemas: RefCell::new(HashMap::new()),
+            read_only_policy: ReadOnlyFieldPolicy::Send,
+            logged_fallbacks: RefCell::new(HashSet::new()),
+            logger: None,
         }
     }
 }
 
 impl _Database {
+    fn set_logger(&mut self, logger: Logger) {
+        self.client.set_logger(logger.clone());
+        self.notification_manager.set_logger(logger.clone());
+        self.logger = Some(logger);
+    }
+
     fn clear_notifications(&self) {
         self.notification_manager.clear();
     }
 
+    fn close_notifications(&self) {
+        self.notification_manager.close_all();
+    }
+
+    fn set_preserve_notifications(&self, preserve: bool) {
+        self.notification_manager.set_preserve_on_clear(preserve);
+    }
+
+    fn reregister_notifications(&self) -> Result<usize> {
+        self.notification_manager.reregister(self.client.clone())
+    }
+
     fn connect(&self) -> Result<()> {
-        return self.client.connect();
+        self.client.connect()
     }
 
     fn connected(&self) -> bool {
@@ -114,66 +818,1387 @@ impl _Database {
         self.client.get_entity(entity_id)
     }
 
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<EntityMetadata> {
+        self.client.get_entity_metadata(entity_id)
+    }
+
+    fn server_info(&self) -> Result<ServerInfo> {
+        self.client.server_info()
+    }
+
+    fn get_entity_schema(&self, entity_type: &str) -> Result<EntitySchema> {
+        self.client.get_entity_schema(entity_type)
+    }
+
+    fn field_exists(&self, entity_type: &str, field: &str) -> Result<bool> {
+        self.client.field_exists(entity_type, field)
+    }
+
+    fn is_field_writable(&self, entity_type: &str, field: &str) -> Result<bool> {
+        self.client.is_field_writable(entity_type, field)
+    }
+
     fn get_entities(&self, entity_type: &str) -> Result<Vec<Entity>> {
         self.client.get_entities(entity_type)
     }
 
-    fn find(
-        &self,
-        entity_type: &str,
-        fields: &Vec<String>,
-        predicate: fn(&HashMap<String, Field>) -> bool,
-    ) -> Result<Vec<Entity>> {
-        let entities = self.get_entities(entity_type)?;
-        let mut result = vec![];
+    fn get_entities_page(&self, entity_type: &str, cursor: Option<&str>) -> Result<EntityPage> {
+        self.client
+            .get_entities_page(entity_type, ENTITY_PAGE_SIZE, cursor)
+    }
 
-        for entity in &entities {
-            let mut requests = vec![];
+    fn validate_notification_config(&self, config: &Config) -> Result<()> {
+        let entity_type = if config.entity_id.is_empty() {
+            config.entity_type.clone()
+        } else {
+            let entity = self.get_entity(&config.entity_id).with_context(|| {
+                format!("Notification entity '{}' could not be found", config.entity_id)
+            })?;
 
-            for field in fields {
-                let field = RawField::new(entity.id.clone(), field.clone());
-                requests.push(Field::new(field));
+            if !config.entity_type.is_empty() && config.entity_type != entity.type_name {
+                return Err(Error::from_notification(&format!(
+                    "Notification entity '{}' is of type '{}', not '{}'",
+                    config.entity_id, entity.type_name, config.entity_type
+                )));
             }
 
-            self.read(&mut requests)?;
+            entity.type_name
+        };
 
-            let mut fields_map = HashMap::new();
-            for field in &requests {
-                fields_map.insert(field.name(), field.clone());
-            }
+        if entity_type.is_empty() {
+            return Err(Error::from_notification(
+                "Notification config names neither an entity nor an entity type",
+            ));
+        }
 
-            if predicate(&fields_map) {
-                result.push(entity.clone());
+        for field in std::iter::once(&config.field).chain(config.context.iter()) {
+            match self.field_exists(&entity_type, field) {
+                Ok(true) => {}
+                Ok(false) => {
+                    return Err(Error::from_notification(&format!(
+                        "Entity type '{}' has no field '{}'",
+                        entity_type, field
+                    )))
+                }
+                Err(e) if Error::is_unsupported(e.as_ref()) => return Ok(()),
+                Err(e) => return Err(e),
             }
         }
 
-        Ok(result)
+        Ok(())
     }
 
-    fn read(&self, requests: &Vec<Field>) -> Result<()> {
-        self.client.read(requests)
-    }
+    fn notification_for_named(
+        &self,
+        entity_type: &str,
+        name: &str,
+        field: &str,
+        notify_on_change: bool,
+    ) -> Result<Config> {
+        let mut matches = self
+            .get_entities(entity_type)?
+            .into_iter()
+            .filter(|e| e.name == name);
 
-    fn write(&self, requests: &Vec<Field>) -> Result<()> {
-        self.client.write(requests)
-    }
+        let entity = match (matches.next(), matches.next()) {
+            (Some(entity), None) => entity,
+            (None, _) => {
+                return Err(Error::from_database_field(&format!(
+                    "No entity of type '{}' is named '{}'",
+                    entity_type, name
+                )))
+            }
+            (Some(_), Some(_)) => {
+                return Err(Error::from_database_field(&format!(
+                    "{} entities of type '{}' are named '{}'",
+                    2 + matches.count(),
+                    entity_type,
+                    name
+                )))
+            }
+        };
 
-    fn register_notification(
-        &self,
-        config: &Config,
-    ) -> Result<Receiver<Notification>> {
-        self.notification_manager
-            .register(self.client.clone(), config)
+        Ok(Config {
+            entity_id: entity.id,
+            entity_type: entity_type.into(),
+            field: field.into(),
+            notify_on_change,
+            context: vec![],
+        })
     }
 
-    fn unregister_notification(&self, token: &Token) -> Result<()> {
+    fn catch_up(&self, configs: &[Config], since: DateTime<Utc>) -> Result<Vec<Notification>> {
+        let mut notifications = vec![];
+
+        for config in configs {
+            let entity_ids = if config.entity_id.is_empty() {
+                self.get_entities(&config.entity_type)?
+                    .into_iter()
+                    .map(|e| e.id)
+                    .collect()
+            } else {
+                vec![config.entity_id.clone()]
+            };
+
+            let requests = entity_ids
+                .iter()
+                .map(|id| RawField::new(id.as_str(), config.field.as_str()).into_field())
+                .collect();
+
+            let token = self
+                .notification_manager
+                .token_for(config)
+                .map(|t| String::from(&t))
+                .unwrap_or_default();
+
+            for (current, previous) in self.read_with_previous(&requests)? {
+                if current.write_time() <= since {
+                    continue;
+                }
+
+                let previous = previous.unwrap_or_else(|| {
+                    RawField::new(current.entity_id(), current.name()).into_field()
+                });
+
+                if config.notify_on_change
+                    && previous.value().into_raw() == current.value().into_raw()
+                {
+                    continue;
+                }
+
+                let context = config
+                    .context
+                    .iter()
+                    .map(|name| RawField::new(current.entity_id(), name.as_str()).into_field())
+                    .collect::<Vec<Field>>();
+                if !context.is_empty() {
+                    self.read(&context)?;
+                }
+
+                notifications.push(Notification {
+                    token: token.clone(),
+                    current,
+                    previous,
+                    context,
+                });
+            }
+        }
+
+        Ok(notifications)
+    }
+
+    fn read_references(&self, entity_id: &str, field: &str) -> Result<Vec<String>> {
+        let request = RawField::new(entity_id, field).into_field();
+        self.read(&vec![request.clone()])?;
+
+        let ids = match request.value().into_raw() {
+            RawValue::Unspecified => vec![],
+            RawValue::EntityReference(id) => vec![id],
+            RawValue::String(ids) => ids.split(',').map(|id| id.trim().to_string()).collect(),
+            _ => {
+                return Err(Error::from_database_field(&format!(
+                    "Field '{}' of entity '{}' does not hold entity references",
+                    field, entity_id
+                )))
+            }
+        };
+
+        Ok(ids.into_iter().filter(|id| !id.is_empty()).collect())
+    }
+
+    fn children(&self, entity_id: &str, children_field: &str) -> Result<Vec<Entity>> {
+        self.read_references(entity_id, children_field)?
+            .iter()
+            .map(|id| self.get_entity(id))
+            .collect()
+    }
+
+    fn parent(&self, entity_id: &str, parent_field: &str) -> Result<Option<Entity>> {
+        match self.read_references(entity_id, parent_field)?.first() {
+            Some(id) => Ok(Some(self.get_entity(id)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Logs at debug level that `method` fell back to doing the work
+    /// locally, only the first time it happens for this database.
+    fn log_fallback_once(&self, method: &'static str, fallback: &str, e: &dyn std::error::Error) {
+        if !self.logged_fallbacks.borrow_mut().insert(method) {
+            return;
+        }
+
+        let c = format!("{}::{}", std::any::type_name::<Self>(), method);
+        if let Some(logger) = &self.logger {
+            logger.debug(&format!("[{}] {}, the client can't do it: {}", c, fallback, e));
+        }
+    }
+
+    fn get_entities_sorted(
+        &self,
+        entity_type: &str,
+        sort_field: &str,
+        ascending: bool,
+    ) -> Result<Vec<Entity>> {
+        match self
+            .client
+            .get_entities_sorted(entity_type, sort_field, ascending)
+        {
+            Err(e) if Error::is_unsupported(e.as_ref()) => {
+                self.log_fallback_once("get_entities_sorted", "Sorting entities locally", e.as_ref());
+
+                let entities = self.get_entities(entity_type)?;
+                let requests: Vec<Field> = entities
+                    .iter()
+                    .map(|entity| entity.field(sort_field))
+                    .collect();
+                self.read(&requests)?;
+
+                let mut sorted: Vec<(Entity, RawValue)> = entities
+                    .into_iter()
+                    .zip(requests.iter().map(|f| f.value().into_raw()))
+                    .collect();
+                sorted.sort_by(|(a, a_value), (b, b_value)| {
+                    let order = match (a_value.is_unspecified(), b_value.is_unspecified()) {
+                        (false, false) if !ascending => compare_for_sort(b_value, a_value),
+                        _ => compare_for_sort(a_value, b_value),
+                    };
+                    order.then_with(|| a.name.cmp(&b.name))
+                });
+
+                Ok(sorted.into_iter().map(|(entity, _)| entity).collect())
+            }
+            result => result,
+        }
+    }
+
+    fn find(
+        &self,
+        entity_type: &str,
+        fields: &Vec<String>,
+        predicate: fn(&HashMap<String, Field>) -> bool,
+        strict: bool,
+    ) -> Result<Vec<Entity>> {
+        let entities = self
+            .get_entities(entity_type)
+            .with_context(|| format!("While listing entities of type '{}'", entity_type))?;
+        let mut result = vec![];
+
+        for entity in &entities {
+            let mut requests = vec![];
+
+            for field in fields {
+                let field = RawField::new(entity.id.clone(), field.clone());
+                requests.push(Field::new(field));
+            }
+
+            if let Err(e) = self.read(&requests) {
+                if strict {
+                    return Err(Error::with_context(
+                        e,
+                        &format!("While searching entities of type '{}'", entity_type),
+                    ));
+                }
+
+                for request in &requests {
+                    if self.read(&vec![request.clone()]).is_err() {
+                        request.update_value(RawValue::Unspecified.into_value());
+                    }
+                }
+            }
+
+            let mut fields_map = HashMap::new();
+            for field in &requests {
+                fields_map.insert(field.name(), field.clone());
+            }
+
+            if predicate(&fields_map) {
+                result.push(entity.clone());
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn read(&self, requests: &Vec<Field>) -> Result<()> {
+        self.client
+            .read(requests)
+            .with_context(|| format!("While reading {}", describe_fields(requests)))
+    }
+
+    fn read_changed_since(
+        &self,
+        requests: &Vec<Field>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Field>> {
+        match self.client.read_changed_since(requests, since) {
+            Err(e) if Error::is_unsupported(e.as_ref()) => {
+                self.log_fallback_once("read_changed_since", "Filtering changed fields locally", e.as_ref());
+                self.read(requests)?;
+
+                Ok(requests
+                    .iter()
+                    .filter(|field| field.write_time() > since)
+                    .cloned()
+                    .collect())
+            }
+            result => result,
+        }
+    }
+
+    fn read_with_previous(&self, requests: &Vec<Field>) -> Result<Vec<(Field, Option<Field>)>> {
+        let previous = match self.client.read_with_previous(requests) {
+            Err(e) if Error::is_unsupported(e.as_ref()) => {
+                self.log_fallback_once("read_with_previous", "Reading without previous values", e.as_ref());
+                self.read(requests)?;
+                vec![None; requests.len()]
+            }
+            result => result
+                .with_context(|| format!("While reading {}", describe_fields(requests)))?,
+        };
+
+        Ok(requests.iter().cloned().zip(previous).collect())
+    }
+
+    fn read_with_deadline(&self, requests: &Vec<Field>, deadline: Instant) -> Result<()> {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::from_timeout("Deadline elapsed before read was issued"));
+        }
+
+        let previous = self.client.timeout();
+        self.client.set_timeout(Some(deadline - now));
+        let result = self.client.read(requests);
+        self.client.set_timeout(previous);
+
+        match result {
+            Err(e) if Instant::now() >= deadline => Err(Error::from_timeout(&format!(
+                "Read did not complete before deadline: {}",
+                e
+            ))),
+            result => result,
+        }
+    }
+
+    fn read_matrix(
+        &self,
+        entity_ids: &[String],
+        fields: &[String],
+    ) -> Result<HashMap<String, HashMap<String, Field>>> {
+        let mut requests = vec![];
+        for entity_id in entity_ids {
+            for field in fields {
+                requests.push(RawField::new(entity_id.clone(), field.clone()).into_field());
+            }
+        }
+
+        self.read(&requests)?;
+
+        let mut result: HashMap<String, HashMap<String, Field>> = HashMap::new();
+        for field in requests {
+            result
+                .entry(field.entity_id())
+                .or_default()
+                .insert(field.name(), field);
+        }
+
+        Ok(result)
+    }
+
+    fn read_entity_values(
+        &self,
+        entity_id: &str,
+        fields: &[String],
+    ) -> Result<HashMap<String, RawValue>> {
+        let requests: Vec<Field> = fields
+            .iter()
+            .map(|field| RawField::new(entity_id, field.clone()).into_field())
+            .collect();
+
+        if !requests.is_empty() {
+            self.read(&requests)?;
+        }
+
+        Ok(requests
+            .into_iter()
+            .map(|field| (field.name(), field.value().into_raw()))
+            .collect())
+    }
+
+    fn read_field_of_type(&self, entity_type: &str, field: &str) -> Result<Vec<RawValue>> {
+        let requests: Vec<Field> = self
+            .get_entities(entity_type)?
+            .iter()
+            .map(|entity| entity.field(field))
+            .collect();
+
+        if !requests.is_empty() {
+            self.read(&requests)?;
+        }
+
+        Ok(requests
+            .iter()
+            .map(|field| field.value().into_raw())
+            .filter(|value| !value.is_unspecified())
+            .collect())
+    }
+
+    fn aggregate_i64(&self, entity_type: &str, field: &str, aggregation: Aggregation) -> Result<i64> {
+        let values = self
+            .read_field_of_type(entity_type, field)?
+            .iter()
+            .map(RawValue::as_i64)
+            .collect::<Result<Vec<i64>>>()?;
+
+        let overflow = || {
+            Error::from_database_field(&format!(
+                "Aggregating field '{}' of '{}' overflowed",
+                field, entity_type
+            ))
+        };
+
+        let sum = || {
+            values
+                .iter()
+                .try_fold(0i64, |acc, v| acc.checked_add(*v))
+                .ok_or_else(overflow)
+        };
+
+        let result = match aggregation {
+            Aggregation::Sum => return Ok(sum()?),
+            Aggregation::Min => values.iter().min().copied(),
+            Aggregation::Max => values.iter().max().copied(),
+            Aggregation::Avg if values.is_empty() => None,
+            Aggregation::Avg => Some(sum()? / values.len() as i64),
+        };
+
+        result.ok_or(Error::from_database_field(&format!(
+            "No values of field '{}' of '{}' to aggregate",
+            field, entity_type
+        )))
+    }
+
+    fn aggregate_f64(&self, entity_type: &str, field: &str, aggregation: Aggregation) -> Result<f64> {
+        let values = self
+            .read_field_of_type(entity_type, field)?
+            .iter()
+            .map(|value| match value {
+                RawValue::Integer(i) => Ok(*i as f64),
+                value => value.as_f64(),
+            })
+            .collect::<Result<Vec<f64>>>()?;
+
+        if values.is_empty() && aggregation != Aggregation::Sum {
+            return Err(Error::from_database_field(&format!(
+                "No values of field '{}' of '{}' to aggregate",
+                field, entity_type
+            )));
+        }
+
+        let sum: f64 = values.iter().sum();
+        Ok(match aggregation {
+            Aggregation::Sum => sum,
+            Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregation::Avg => sum / values.len() as f64,
+        })
+    }
+
+    fn snapshot(&self, entity_ids: &[String], fields: &[String]) -> Result<Snapshot> {
+        let mut snapshot = Snapshot::new();
+        for fields in self.read_matrix(entity_ids, fields)?.values() {
+            for field in fields.values() {
+                snapshot.insert(field);
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    fn entity_fingerprint(&self, entity_id: &str, fields: &[String]) -> Result<u64> {
+        let mut names = fields.to_vec();
+        names.sort();
+        names.dedup();
+
+        let requests: Vec<Field> = names
+            .iter()
+            .map(|name| RawField::new(entity_id, name.clone()).into_field())
+            .collect();
+        self.read(&requests)?;
+
+        Ok(fingerprint_fields(&requests))
+    }
+
+    fn read_expecting(&self, requests: &[(Field, ValueKind)]) -> Result<()> {
+        let fields: Vec<Field> = requests.iter().map(|(f, _)| f.clone()).collect();
+        self.read(&fields)?;
+
+        for (field, expected) in requests {
+            let actual = field.value().kind();
+            if actual != *expected {
+                return Err(Error::from_database_field(&format!(
+                    "Field '{}' of entity '{}' is {:?}, expected {:?}",
+                    field.name(),
+                    field.entity_id(),
+                    actual,
+                    expected
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks up the schema of `field` of `entity_id`, caching the entity's
+    /// type and schema. Clients without schemas report every field as
+    /// unknown.
+    fn field_schema(&self, entity_id: &str, field: &str) -> Result<Option<FieldSchema>> {
+        let cached_type = self.entity_types.borrow().get(entity_id).cloned();
+        let entity_type = match cached_type {
+            Some(entity_type) => entity_type,
+            None => {
+                let entity_type = self.client.get_entity(entity_id)?.type_name;
+                self.entity_types
+                    .borrow_mut()
+                    .insert(entity_id.to_string(), entity_type.clone());
+                entity_type
+            }
+        };
+
+        if !self.entity_schemas.borrow().contains_key(&entity_type) {
+            let schema = match self.client.get_entity_schema(&entity_type) {
+                Ok(schema) => schema,
+                Err(e) if Error::is_unsupported(e.as_ref()) => EntitySchema {
+                    name: entity_type.clone(),
+                    fields: vec![],
+                },
+                Err(e) => return Err(e),
+            };
+
+            self.entity_schemas.borrow_mut().insert(entity_type.clone(), schema);
+        }
+
+        Ok(self.entity_schemas.borrow()[&entity_type].field(field).cloned())
+    }
+
+    fn declared_kind(&self, entity_id: &str, field: &str) -> Result<Option<ValueKind>> {
+        Ok(self
+            .field_schema(entity_id, field)?
+            .and_then(|schema| kind_of_type_name(&schema.type_name)))
+    }
+
+    fn filter_read_only(&self, requests: &[Field]) -> Result<Vec<Field>> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "filter_read_only");
+
+        if self.read_only_policy == ReadOnlyFieldPolicy::Send {
+            return Ok(requests.to_vec());
+        }
+
+        let mut writable = vec![];
+        for field in requests {
+            let read_only = self
+                .field_schema(&field.entity_id(), &field.name())?
+                .is_some_and(|schema| schema.read_only);
+
+            if !read_only {
+                writable.push(field.clone());
+                continue;
+            }
+
+            match self.read_only_policy {
+                ReadOnlyFieldPolicy::Fail => {
+                    return Err(Error::from_database_field(&format!(
+                        "Field '{}' of entity '{}' is read-only",
+                        field.name(),
+                        field.entity_id()
+                    )))
+                }
+                _ => {
+                    if let Some(logger) = &self.logger {
+                        logger.warning(&format!(
+                            "[{}] Skipping write of read-only field '{}' of entity '{}'",
+                            c,
+                            field.name(),
+                            field.entity_id()
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(writable)
+    }
+
+    fn coerce_writes(&self, requests: &[Field]) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "coerce_writes");
+
+        if !self.auto_coerce_writes {
+            return Ok(());
+        }
+
+        for field in requests {
+            let Some(kind) = self.declared_kind(&field.entity_id(), &field.name())? else {
+                continue;
+            };
+
+            let value = field.value().into_raw();
+            if value.kind() == kind || value.is_unspecified() {
+                continue;
+            }
+
+            let coerced = value.coerce_to(kind).with_context(|| {
+                format!(
+                    "While coercing field '{}' of entity '{}' to its declared type",
+                    field.name(),
+                    field.entity_id()
+                )
+            })?;
+
+            if let Some(logger) = &self.logger {
+                logger.debug(&format!(
+                    "[{}] Coerced {:?} value of field '{}' of entity '{}' to {:?}",
+                    c,
+                    value.kind(),
+                    field.name(),
+                    field.entity_id(),
+                    kind
+                ));
+            }
+
+            field.update_value(coerced.into_value());
+        }
+
+        Ok(())
+    }
+
+    fn dedup_writes(&self, requests: &Vec<Field>) -> Result<Vec<Field>> {
+        if !self.validate_writes {
+            return Ok(requests.clone());
+        }
+
+        let mut seen: HashMap<(String, String), RawValue> = HashMap::new();
+        let mut deduped = vec![];
+        for field in requests {
+            let key = (field.entity_id(), field.name());
+            let value = field.value().into_raw();
+
+            match seen.get(&key) {
+                Some(existing) if *existing == value => {}
+                Some(_) => {
+                    return Err(Error::from_conflicting_writes(&format!(
+                        "Field '{}' of entity '{}' is written more than once with different values",
+                        key.1, key.0
+                    )))
+                }
+                None => {
+                    seen.insert(key, value);
+                    deduped.push(field.clone());
+                }
+            }
+        }
+
+        Ok(deduped)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    fn write(&self, requests: &Vec<Field>) -> Result<()> {
+        let writable = self.filter_read_only(requests)?;
+        if writable.is_empty() {
+            return Ok(());
+        }
+
+        self.coerce_writes(&writable)?;
+        self.client
+            .write(&self.dedup_writes(&writable)?)
+            .with_context(|| format!("While writing {}", describe_fields(&writable)))
+    }
+
+    fn write_at(&self, requests: &Vec<Field>, write_time: DateTime<Utc>) -> Result<()> {
+        for field in requests {
+            field.update_write_time(write_time);
+        }
+
+        let writable = self.filter_read_only(requests)?;
+        if writable.is_empty() {
+            return Ok(());
+        }
+
+        self.coerce_writes(&writable)?;
+        self.client
+            .write_at(&self.dedup_writes(&writable)?, write_time)
+            .with_context(|| format!("While writing {}", describe_fields(&writable)))
+    }
+
+    fn touch_field(&self, entity_id: &str, field: &str) -> Result<()> {
+        let field = RawField::new(entity_id, field).into_field();
+        self.read(&vec![field.clone()])?;
+        field.touch();
+        self.write_at(&vec![field.clone()], field.write_time())
+    }
+
+    fn swap_fields(&self, a: (&str, &str), b: (&str, &str)) -> Result<()> {
+        let a = RawField::new(a.0, a.1).into_field();
+        let b = RawField::new(b.0, b.1).into_field();
+        self.read(&vec![a.clone(), b.clone()])?;
+
+        let a_value = a.value().into_raw();
+        let b_value = b.value().into_raw();
+        if a_value.is_unspecified() && b_value.is_unspecified() {
+            return Ok(());
+        }
+
+        if a_value.kind() != b_value.kind() {
+            return Err(Error::from_database_field(&format!(
+                "Cannot swap field '{}' of entity '{}' ({:?}) with field '{}' of entity '{}' ({:?})",
+                a.name(),
+                a.entity_id(),
+                a_value.kind(),
+                b.name(),
+                b.entity_id(),
+                b_value.kind()
+            )));
+        }
+
+        a.update_value(b_value.into_value());
+        b.update_value(a_value.into_value());
+        self.write(&vec![a, b])
+    }
+
+    fn write_and_verify(&self, field: Field) -> Result<()> {
+        let written = field.value().into_raw();
+        self.write(&vec![field.clone()])?;
+
+        let readback = RawField::new(field.entity_id(), field.name()).into_field();
+        self.read(&vec![readback.clone()])?;
+
+        let actual = readback.value().into_raw();
+        if !actual.loosely_eq(&written) {
+            return Err(Error::from_verification_failed(&format!(
+                "Field '{}' of entity '{}' holds {:?} after writing {:?}",
+                field.name(),
+                field.entity_id(),
+                actual,
+                written
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn write_if_unchanged(&self, field: &Field, expected: &RawValue) -> Result<bool> {
+        let current = RawField::new(field.entity_id(), field.name()).into_field();
+        self.read(&vec![current.clone()])?;
+
+        if current.value().into_raw() != *expected {
+            return Ok(false);
+        }
+
+        self.write(&vec![field.clone()])?;
+        Ok(true)
+    }
+
+    fn is_in_sync(&self, field: &Field) -> Result<bool> {
+        let current = RawField::new(field.entity_id(), field.name()).into_field();
+        self.read(&vec![current.clone()])?;
+
+        Ok(current.value().into_raw() == field.value().into_raw())
+    }
+
+    fn increment(&self, entity_id: &str, field: &str, delta: i64) -> Result<i64> {
+        for _ in 0..INCREMENT_MAX_ATTEMPTS {
+            let current = RawField::new(entity_id, field).into_field();
+            self.read(&vec![current.clone()])?;
+
+            let previous = current.value().into_raw();
+            let value = match &previous {
+                RawValue::Unspecified => delta,
+                v => v.as_i64()?.checked_add(delta).ok_or(Error::from_database_field(
+                    &format!("Incrementing field '{}' of entity '{}' overflows", field, entity_id),
+                ))?,
+            };
+
+            current.set_i64_value(value);
+            if self.write_if_unchanged(&current, &previous)? {
+                return Ok(value);
+            }
+        }
+
+        Err(Error::from_database_field(&format!(
+            "Field '{}' of entity '{}' kept changing during increment after {} attempts",
+            field, entity_id, INCREMENT_MAX_ATTEMPTS
+        )))
+    }
+
+    fn ensure_field(&self, entity_id: &str, field: &str, default: RawValue) -> Result<bool> {
+        let field = RawField::new(entity_id, field).into_field();
+        field.update_value(default.into_value());
+        self.write_if_unchanged(&field, &RawValue::Unspecified)
+    }
+
+    fn writer_name(&self, field: &Field) -> Result<String> {
+        let writer_id = field.writer_id();
+        if writer_id.is_empty() {
+            return Ok(SYSTEM_WRITER_NAME.to_string());
+        }
+
+        if let Some(name) = self.writer_names.borrow().get(&writer_id) {
+            return Ok(name.clone());
+        }
+
+        let name = self.get_entity(&writer_id)?.name;
+        self.writer_names.borrow_mut().insert(writer_id, name.clone());
+        Ok(name)
+    }
+
+    fn register_notification(
+        &self,
+        config: &Config,
+    ) -> Result<Receiver<Notification>> {
+        self.notification_manager
+            .register(self.client.clone(), config)
+    }
+
+    fn register_notification_bounded(
+        &self,
+        config: &Config,
+        capacity: usize,
+    ) -> Result<BoundedReceiver<Notification>> {
+        self.notification_manager
+            .register_bounded(self.client.clone(), config, capacity)
+    }
+
+    fn register_filtered(
+        &self,
+        config: &Config,
+        context: &[&str],
+        filter: fn(&Notification) -> bool,
+    ) -> Result<Receiver<Notification>> {
+        self.notification_manager
+            .register_filtered(self.client.clone(), config, context, filter)
+    }
+
+    fn register_notification_sink(
+        &self,
+        config: &Config,
+        sink: Box<dyn NotificationSink>,
+    ) -> Result<SinkHandle> {
+        self.notification_manager
+            .register_sink(self.client.clone(), config, sink)
+    }
+
+    fn unregister_notification_sink(&self, handle: &SinkHandle) -> Result<()> {
+        self.notification_manager
+            .unregister_sink(self.client.clone(), handle)
+    }
+
+    fn register_notifications_multiplexed(
+        &self,
+        configs: &[Config],
+    ) -> Result<(MultiplexedReceiver, Vec<SinkHandle>)> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "register_notifications_multiplexed");
+        let (sender, receiver) = channel();
+        let mut handles = vec![];
+
+        for config in configs {
+            let sink = Multiplexed {
+                config: config.clone(),
+                sender: sender.clone(),
+            };
+
+            let result = self
+                .register_notification_sink(config, Box::new(sink))
+                .with_context(|| {
+                    format!(
+                        "While registering notification for field '{}' of entity '{}'",
+                        config.field, config.entity_id
+                    )
+                });
+
+            match result {
+                Ok(handle) => handles.push(handle),
+                Err(e) => {
+                    for handle in &handles {
+                        if let Err(e) = self.unregister_notification_sink(handle) {
+                            if let Some(logger) = &self.logger {
+                                logger.warning(&format!(
+                                    "[{}] Failed to unregister notification for field '{}' of entity '{}': {}",
+                                    c, handle.config().field, handle.config().entity_id, e
+                                ));
+                            }
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok((receiver, handles))
+    }
+
+    fn unregister_notification(&self, token: &Token) -> Result<()> {
         self.notification_manager
             .unregister(self.client.clone(), token)
     }
 
+    fn export_notification_configs(&self) -> Vec<Config> {
+        self.notification_manager.export_configs()
+    }
+
+    fn restore_notifications(
+        &self,
+        configs: &[Config],
+    ) -> Result<Vec<Receiver<Notification>>> {
+        configs
+            .iter()
+            .map(|config| self.register_notification(config))
+            .collect()
+    }
+
+    fn unregister_notifications_by_type(&self, entity_type: &str) -> Result<usize> {
+        self.notification_manager
+            .unregister_by_type(self.client.clone(), entity_type)
+    }
+
+    fn renew_expiring_notifications(&self, within: Duration) -> Result<usize> {
+        self.notification_manager
+            .renew_expiring(self.client.clone(), within)
+    }
+
+    fn set_dead_consumer_timeout(&self, timeout: Option<Duration>) {
+        self.notification_manager.set_dead_consumer_timeout(timeout);
+    }
+
+    fn set_notification_full_channel_policy(&self, policy: FullChannelPolicy) -> Result<()> {
+        self.notification_manager.set_full_channel_policy(policy)
+    }
+
+    fn set_notification_fetch_timeout(&self, timeout: Option<Duration>) {
+        self.notification_manager.set_fetch_timeout(timeout);
+    }
+
+    fn set_notification_longpoll_wait(&self, wait: Option<Duration>) {
+        self.notification_manager.set_longpoll_wait(wait);
+    }
+
+    fn set_suppress_loosely_unchanged_notifications(&self, suppress: bool) {
+        self.notification_manager.set_suppress_loosely_unchanged(suppress);
+    }
+
+    fn set_drop_stale_notifications(&self, drop_stale: bool) {
+        self.notification_manager.set_drop_stale(drop_stale);
+    }
+
+    fn subscribe_all_notifications(&self) -> Receiver<Notification> {
+        self.notification_manager.subscribe_all()
+    }
+
+    fn notification_backlog(&self) -> Result<usize> {
+        self.client.notification_backlog()
+    }
+
     fn process_notifications(&self) -> Result<()> {
-        return self
-            .notification_manager
-            .process_notifications(self.client.clone());
+        self.notification_manager
+            .process_notifications(self.client.clone())
+    }
+
+    fn notification_stats(&self) -> NotificationStats {
+        self.notification_manager.stats()
     }
-}
\ No newline at end of file
+
+    fn reset_notification_stats(&self) {
+        self.notification_manager.reset_stats();
+    }
+
+    fn drain_notifications(&self, timeout: Duration) -> Result<()> {
+        self.notification_manager
+            .drain(self.client.clone(), timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use chrono::TimeDelta;
+
+    fn mock_database() -> (mock::Client, Database) {
+        let mock = mock::Client::new();
+        (mock.clone(), Database::new(Client::new(mock)))
+    }
+
+    fn is_open(fields: &HashMap<String, Field>) -> bool {
+        fields["State"].value().as_str().is_ok_and(|s| s == "Open")
+    }
+
+    fn door_config(field: &str) -> Config {
+        Config {
+            entity_id: "Door1".to_string(),
+            entity_type: "Door".to_string(),
+            field: field.to_string(),
+            notify_on_change: false,
+            context: vec![],
+        }
+    }
+
+    fn door_notification(token: &str, field: &str) -> Notification {
+        Notification::new(
+            token,
+            RawField::new_with_value("Door1", field, RawValue::from("Open")),
+            RawField::new("Door1", field),
+            vec![],
+        )
+    }
+
+    struct Lines(Rc<RefCell<Vec<String>>>);
+
+    impl crate::loggers::common::LoggerTrait for Lines {
+        fn log(&self, _level: &crate::loggers::common::LogLevel, message: &str) {
+            self.0.borrow_mut().push(message.to_string());
+        }
+
+        fn level(&self) -> crate::loggers::common::LogLevel {
+            crate::loggers::common::LogLevel::Debug
+        }
+    }
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+
+    impl NotificationSink for Collect {
+        fn deliver(&mut self, notification: &Notification) {
+            self.0.borrow_mut().push(notification.current.name());
+        }
+    }
+
+    #[test]
+    fn find_passes_fields_missing_from_a_type_as_unspecified() {
+        let (mock, db) = mock_database();
+        mock.set_fail_unset_reads(true);
+        mock.add_entity(Entity::new("Door1", "Door", "Front"));
+        mock.add_entity(Entity::new("Door2", "Door", "Back"));
+        mock.set_value("Door1", "State", RawValue::from("Open"));
+        mock.set_value("Door1", "Alarm", RawValue::from("Armed"));
+        // Door2 has no Alarm field
+        mock.set_value("Door2", "State", RawValue::from("Open"));
+
+        let fields = vec!["State".to_string(), "Alarm".to_string()];
+        let found = db.find("Door", &fields, is_open, false).unwrap();
+        assert_eq!(found.iter().map(|e| e.id()).collect::<Vec<_>>(), vec!["Door1", "Door2"]);
+
+        let found = db
+            .find("Door", &fields, |f| f["Alarm"].value().into_raw().is_unspecified(), false)
+            .unwrap();
+        assert_eq!(found.iter().map(|e| e.id()).collect::<Vec<_>>(), vec!["Door2"]);
+    }
+
+    #[test]
+    fn get_entities_sorted_falls_back_to_sorting_by_field() {
+        let (mock, db) = mock_database();
+        mock.add_entity(Entity::new("Sensor1", "Sensor", "A"));
+        mock.add_entity(Entity::new("Sensor2", "Sensor", "B"));
+        mock.add_entity(Entity::new("Sensor3", "Sensor", "C"));
+        mock.add_entity(Entity::new("Sensor4", "Sensor", "D"));
+        mock.set_value("Sensor1", "Reading", RawValue::Float(2.5));
+        mock.set_value("Sensor2", "Reading", RawValue::Integer(1));
+        mock.set_value("Sensor4", "Reading", RawValue::Integer(3));
+
+        let ids = |entities: Vec<Entity>| entities.iter().map(|e| e.id()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(db.get_entities_sorted("Sensor", "Reading", true).unwrap()),
+            vec!["Sensor2", "Sensor1", "Sensor4", "Sensor3"]
+        );
+        assert_eq!(
+            ids(db.get_entities_sorted("Sensor", "Reading", false).unwrap()),
+            vec!["Sensor4", "Sensor1", "Sensor2", "Sensor3"]
+        );
+    }
+
+    #[test]
+    fn local_fallbacks_are_logged_once() {
+        let (mock, db) = mock_database();
+        let lines = Rc::new(RefCell::new(vec![]));
+        db.set_logger(Logger::new(Lines(lines.clone())));
+        mock.add_entity(Entity::new("Sensor1", "Sensor", "A"));
+
+        for _ in 0..3 {
+            db.get_entities_sorted("Sensor", "Reading", true).unwrap();
+            db.read_with_previous(&vec![RawField::new("Sensor1", "Reading").into_field()])
+                .unwrap();
+        }
+
+        let logged = |text: &str| lines.borrow().iter().filter(|l| l.contains(text)).count();
+        assert_eq!(logged("Sorting entities locally"), 1);
+        assert_eq!(logged("Reading without previous values"), 1);
+    }
+
+    #[test]
+    fn find_fails_on_fields_missing_from_a_type_when_strict() {
+        let (mock, db) = mock_database();
+        mock.set_fail_unset_reads(true);
+        mock.add_entity(Entity::new("Door1", "Door", "Front"));
+        mock.set_value("Door1", "State", RawValue::from("Open"));
+
+        let fields = vec!["State".to_string(), "Alarm".to_string()];
+        assert!(db.find("Door", &fields, is_open, true).is_err());
+    }
+
+    #[test]
+    fn unregister_notification_sink_stops_delivery_and_unregisters() {
+        let (mock, db) = mock_database();
+        let delivered = Rc::new(RefCell::new(vec![]));
+
+        let handle = db
+            .register_notification_sink(&door_config("State"), Box::new(Collect(delivered.clone())))
+            .unwrap();
+        mock.push_notification(door_notification("mock-1", "State"));
+        db.process_notifications().unwrap();
+        assert_eq!(*delivered.borrow(), vec!["State".to_string()]);
+
+        db.unregister_notification_sink(&handle).unwrap();
+        assert_eq!(
+            mock.operations().last(),
+            Some(&mock::Operation::UnregisterNotification(Token::from("mock-1")))
+        );
+        assert!(db.unregister_notification_sink(&handle).is_err());
+    }
+
+    #[test]
+    fn unregister_notification_sink_keeps_registrations_still_in_use() {
+        let (mock, db) = mock_database();
+        let receiver = db.register_notification(&door_config("State")).unwrap();
+        let handle = db
+            .register_notification_sink(&door_config("State"), Box::new(Collect(Rc::default())))
+            .unwrap();
+
+        db.unregister_notification_sink(&handle).unwrap();
+        mock.push_notification(door_notification("mock-1", "State"));
+        db.process_notifications().unwrap();
+
+        assert!(receiver.try_recv().is_ok());
+        assert!(!mock
+            .operations()
+            .iter()
+            .any(|op| matches!(op, mock::Operation::UnregisterNotification(_))));
+    }
+
+    #[test]
+    fn register_notifications_multiplexed_returns_a_handle_per_config() {
+        let (mock, db) = mock_database();
+        let configs = [door_config("State"), door_config("Alarm")];
+
+        let (receiver, handles) = db.register_notifications_multiplexed(&configs).unwrap();
+        assert_eq!(
+            handles.iter().map(|h| h.config()).collect::<Vec<_>>(),
+            configs.iter().collect::<Vec<_>>()
+        );
+
+        mock.push_notification(door_notification("mock-2", "Alarm"));
+        db.process_notifications().unwrap();
+        assert_eq!(receiver.try_recv().unwrap().0, configs[1]);
+
+        for handle in &handles {
+            db.unregister_notification_sink(handle).unwrap();
+        }
+        let unregistered = mock
+            .operations()
+            .into_iter()
+            .filter(|op| matches!(op, mock::Operation::UnregisterNotification(_)))
+            .count();
+        assert_eq!(unregistered, 2);
+    }
+
+    #[test]
+    fn renew_expiring_notifications_reports_failures_after_renewing_the_rest() {
+        let (mock, db) = mock_database();
+        mock.set_lease_duration(Some(TimeDelta::seconds(30)));
+        let configs = [door_config("State"), door_config("Alarm"), door_config("Lock")];
+        let receivers = db.restore_notifications(&configs).unwrap();
+
+        mock.fail_registrations_of("Alarm");
+        let e = db
+            .renew_expiring_notifications(Duration::from_secs(60))
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("Renewed 2 of 3"), "{}", e);
+        assert!(e.contains("field 'Alarm'"), "{}", e);
+
+        let renewed = |field: &str| {
+            db.0.borrow()
+                .notification_manager
+                .token_for(&door_config(field))
+                .unwrap()
+        };
+        assert_eq!(renewed("Alarm"), Token::from("mock-2"));
+        assert_ne!(renewed("State"), Token::from("mock-1"));
+        assert_ne!(renewed("Lock"), Token::from("mock-3"));
+
+        mock.push_notification(door_notification(&String::from(&renewed("Lock")), "Lock"));
+        db.process_notifications().unwrap();
+        assert!(receivers[2].try_recv().is_ok());
+    }
+
+    #[test]
+    fn bounded_notification_receivers_follow_the_full_channel_policy() {
+        let (mock, db) = mock_database();
+        assert!(db
+            .set_notification_full_channel_policy(FullChannelPolicy::Block)
+            .is_err_and(|e| Error::is_unsupported(e.as_ref())));
+
+        let receiver = db
+            .register_notification_bounded(&door_config("State"), 1)
+            .unwrap();
+        db.set_notification_full_channel_policy(FullChannelPolicy::DropOldest)
+            .unwrap();
+
+        let newest = Notification::new(
+            "mock-1",
+            RawField::new_with_value("Door1", "State", RawValue::from("Closed")),
+            RawField::new("Door1", "State"),
+            vec![],
+        );
+        mock.push_notification(door_notification("mock-1", "State"));
+        mock.push_notification(newest);
+        db.process_notifications().unwrap();
+
+        assert_eq!(
+            receiver.try_recv().unwrap().current.value().as_str().unwrap(),
+            "Closed"
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn full_consumers_are_evicted_after_the_dead_consumer_timeout() {
+        let (mock, db) = mock_database();
+        db.set_dead_consumer_timeout(Some(Duration::from_millis(20)));
+        let receiver = db
+            .register_notification_bounded(&door_config("State"), 1)
+            .unwrap();
+
+        // The default policy would disconnect it right away; the timeout wins
+        for _ in 0..2 {
+            mock.push_notification(door_notification("mock-1", "State"));
+            db.process_notifications().unwrap();
+        }
+        assert!(!mock
+            .operations()
+            .iter()
+            .any(|op| matches!(op, mock::Operation::UnregisterNotification(_))));
+
+        std::thread::sleep(Duration::from_millis(30));
+        mock.push_notification(door_notification("mock-1", "State"));
+        db.process_notifications().unwrap();
+
+        assert_eq!(
+            mock.operations().last(),
+            Some(&mock::Operation::UnregisterNotification(Token::from("mock-1")))
+        );
+        assert!(receiver.try_recv().is_ok());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn notifications_under_a_renewed_token_are_dropped_without_failing() {
+        let (mock, db) = mock_database();
+        mock.set_lease_duration(Some(TimeDelta::seconds(30)));
+        let receiver = db.register_notification(&door_config("State")).unwrap();
+        db.renew_expiring_notifications(Duration::from_secs(60)).unwrap();
+
+        mock.push_notification(door_notification("mock-1", "State"));
+        mock.push_notification(door_notification("mock-2", "State"));
+        db.process_notifications().unwrap();
+
+        assert_eq!(receiver.try_recv().unwrap().token, "mock-2");
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(db.notification_stats().dropped, 1);
+        assert_eq!(db.notification_stats().emitted, 1);
+    }
+
+    #[test]
+    fn ensure_field_writes_the_default_only_when_unset() {
+        let (mock, db) = mock_database();
+
+        assert!(db.ensure_field("Door1", "State", RawValue::from("Closed")).unwrap());
+        mock.assert_wrote("Door1", "State", RawValue::from("Closed"));
+        mock.take_operations();
+
+        assert!(!db.ensure_field("Door1", "State", RawValue::from("Open")).unwrap());
+        mock.assert_read("Door1", "State");
+        mock.assert_no_writes();
+        assert_eq!(mock.value("Door1", "State"), Some(RawValue::from("Closed")));
+    }
+
+    #[test]
+    fn write_sends_nothing_when_every_field_is_skipped() {
+        let (mock, db) = mock_database();
+        db.set_read_only_field_policy(ReadOnlyFieldPolicy::Skip);
+        mock.add_entity(Entity::new("Door1", "Door", "Front"));
+        mock.add_schema(EntitySchema {
+            name: "Door".to_string(),
+            fields: vec![FieldSchema {
+                name: "OpenCount".to_string(),
+                type_name: "qdb.Int".to_string(),
+                read_only: true,
+            }],
+        });
+
+        let field = RawField::new_with_value("Door1", "OpenCount", RawValue::Integer(3)).into_field();
+        db.write(&vec![field.clone()]).unwrap();
+        db.write_at(&vec![field], Utc::now()).unwrap();
+
+        assert!(mock.operations().is_empty());
+    }
+
+    struct WriterNames(Database, Rc<RefCell<Vec<String>>>);
+
+    impl NotificationSink for WriterNames {
+        fn deliver(&mut self, notification: &Notification) {
+            self.1.borrow_mut().push(self.0.writer_name(&notification.current).unwrap());
+        }
+    }
+
+    #[test]
+    fn writer_name_can_be_resolved_while_processing_notifications() {
+        let (mock, db) = mock_database();
+        mock.add_entity(Entity::new("User1", "User", "Alice"));
+        let names = Rc::new(RefCell::new(vec![]));
+
+        db.register_notification_sink(
+            &door_config("State"),
+            Box::new(WriterNames(db.clone(), names.clone())),
+        )
+        .unwrap();
+
+        let notification = door_notification("mock-1", "State");
+        notification.current.with_writer("User1");
+        mock.push_notification(notification);
+        db.process_notifications().unwrap();
+
+        assert_eq!(*names.borrow(), vec!["Alice".to_string()]);
+    }
+
+    #[test]
+    fn fingerprints_are_pinned() {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.write(b"a");
+        // The published FNV-1a test vector for "a"
+        assert_eq!(fingerprint.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let written = DateTime::parse_from_rfc3339("2024-05-01T08:30:00.5Z").unwrap().to_utc();
+        let field = |name: &str, value: RawValue| {
+            let field = RawField::new_with_value("Door1", name, value).into_field();
+            field.update_write_time(written);
+            field
+        };
+        let fields = vec![
+            field("Count", RawValue::Integer(3)),
+            field("Open", RawValue::Boolean(true)),
+            field("State", RawValue::from("Open")),
+        ];
+
+        assert_eq!(fingerprint_fields(&fields), 18_360_946_787_693_710_347);
+        assert_ne!(fingerprint_fields(&fields[..2]), 18_360_946_787_693_710_347);
+        assert_eq!(
+            fingerprint_fields(&[field("Level", RawValue::Float(-0.0))]),
+            fingerprint_fields(&[field("Level", RawValue::Float(0.0))])
+        );
+    }
+}
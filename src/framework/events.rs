@@ -1 +1,3 @@
-pub mod emitter;
\ No newline at end of file
+pub mod bounded;
+pub mod emitter;
+pub mod stream;
\ No newline at end of file
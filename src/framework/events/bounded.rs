@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// What an emitter does when a bounded receiver has no room for a new value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullChannelPolicy {
+    /// Discards the value being emitted.
+    DropNewest,
+    /// Discards the oldest queued value to make room.
+    DropOldest,
+    /// Waits until the receiver makes room. Only safe when the receiver is
+    /// drained on another thread: drained on the emitting thread, the wait
+    /// never ends. Notification receivers can't use it for that reason.
+    Block,
+    /// Disconnects the receiver, as if it had been dropped.
+    DisconnectSlow,
+}
+
+struct State<T> {
+    items: VecDeque<T>,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+pub struct BoundedSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct BoundedReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Creates a channel holding at most `capacity` values (at least one). It
+/// mirrors the `std::sync::mpsc` receiving API, but lets the sender decide
+/// what happens when it is full.
+pub fn bounded<T>(capacity: usize) -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            items: VecDeque::new(),
+            sender_alive: true,
+            receiver_alive: true,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        capacity: capacity.max(1),
+    });
+
+    (
+        BoundedSender {
+            shared: shared.clone(),
+        },
+        BoundedReceiver { shared },
+    )
+}
+
+impl<T> BoundedSender<T> {
+    /// Queues `value` following `policy` when full. Returns false once the
+    /// receiver is gone or was disconnected by `DisconnectSlow`.
+    pub fn send(&self, value: T, policy: FullChannelPolicy) -> bool {
+        let mut state = self.shared.lock();
+
+        while state.receiver_alive && state.items.len() >= self.shared.capacity {
+            match policy {
+                FullChannelPolicy::DropNewest => return true,
+                FullChannelPolicy::DropOldest => {
+                    state.items.pop_front();
+                }
+                FullChannelPolicy::Block => {
+                    state = match self.shared.not_full.wait(state) {
+                        Ok(guard) => guard,
+                        Err(poisoned) => poisoned.into_inner(),
+                    };
+                }
+                FullChannelPolicy::DisconnectSlow => return false,
+            }
+        }
+
+        if !state.receiver_alive {
+            return false;
+        }
+
+        state.items.push_back(value);
+        self.shared.not_empty.notify_one();
+        true
+    }
+}
+
+impl<T> BoundedSender<T> {
+    pub fn is_full(&self) -> bool {
+        self.shared.lock().items.len() >= self.shared.capacity
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        self.shared.lock().sender_alive = false;
+        self.shared.not_empty.notify_all();
+    }
+}
+
+impl<T> BoundedReceiver<T> {
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.lock();
+
+        match state.items.pop_front() {
+            Some(value) => {
+                self.shared.not_full.notify_one();
+                Ok(value)
+            }
+            None if state.sender_alive => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(value) = state.items.pop_front() {
+                self.shared.not_full.notify_one();
+                return Ok(value);
+            }
+
+            if !state.sender_alive {
+                return Err(RecvError);
+            }
+
+            state = match self.shared.not_empty.wait(state) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(value) = state.items.pop_front() {
+                self.shared.not_full.notify_one();
+                return Ok(value);
+            }
+
+            if !state.sender_alive {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            state = match self.shared.not_empty.wait_timeout(state, deadline - now) {
+                Ok((guard, _)) => guard,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+    }
+
+    /// Number of values currently queued.
+    pub fn len(&self) -> usize {
+        self.shared.lock().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for BoundedReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.lock().receiver_alive = false;
+        self.shared.not_full.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn block_waits_for_a_receiver_drained_on_another_thread() {
+        let (sender, receiver) = bounded(1);
+        let consumer = thread::spawn(move || {
+            let mut received = vec![];
+            while let Ok(value) = receiver.recv() {
+                received.push(value);
+            }
+            received
+        });
+
+        for value in 0..5 {
+            assert!(sender.send(value, FullChannelPolicy::Block));
+        }
+        drop(sender);
+
+        assert_eq!(consumer.join().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn drop_policies_keep_the_queue_within_capacity() {
+        let (sender, receiver) = bounded(2);
+        for value in 0..4 {
+            assert!(sender.send(value, FullChannelPolicy::DropOldest));
+        }
+        assert_eq!(receiver.len(), 2);
+        assert_eq!(receiver.try_recv(), Ok(2));
+
+        assert!(sender.send(4, FullChannelPolicy::DropNewest));
+        assert!(sender.send(5, FullChannelPolicy::DropNewest));
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_eq!(receiver.try_recv(), Ok(4));
+
+        assert!(sender.send(6, FullChannelPolicy::DisconnectSlow));
+        assert!(sender.send(7, FullChannelPolicy::DisconnectSlow));
+        assert!(!sender.send(8, FullChannelPolicy::DisconnectSlow));
+    }
+}
@@ -1,35 +1,179 @@
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
 
+use crate::framework::events::bounded::{bounded, BoundedReceiver, BoundedSender, FullChannelPolicy};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SlotToken(usize);
 
+pub type Callback<T> = Box<dyn FnMut(&T)>;
+
+enum Slot<T> {
+    Unbounded(Sender<T>),
+    Bounded(BoundedSender<T>, Option<Instant>),
+    Callback(Callback<T>),
+}
+
 pub struct Emitter<T> {
-    senders: HashMap<SlotToken, Sender<T>>,
+    senders: HashMap<SlotToken, Slot<T>>,
+    filters: HashMap<SlotToken, fn(&T) -> bool>,
+    full_channel_policy: FullChannelPolicy,
+    stall_timeout: Option<Duration>,
     args: std::marker::PhantomData<T>,
 }
 
 impl<T> Emitter<T> {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Emitter {
             senders: HashMap::new(),
+            filters: HashMap::new(),
+            full_channel_policy: FullChannelPolicy::DisconnectSlow,
+            stall_timeout: None,
             args: std::marker::PhantomData,
         }
     }
+
+    /// Chooses how `emit` treats bounded receivers that are full. Defaults
+    /// to `DisconnectSlow`. Unbounded receivers are never full.
+    pub fn set_full_channel_policy(&mut self, policy: FullChannelPolicy) {
+        self.full_channel_policy = policy;
+    }
+
+    /// Disconnects bounded receivers that have stayed full for longer than
+    /// `timeout`, as seen by `emit`. While set, the timeout alone decides
+    /// eviction: until it passes, a full receiver drops values (the oldest
+    /// under `DropOldest`, otherwise the newest) instead of blocking or
+    /// being disconnected.
+    pub fn set_stall_timeout(&mut self, timeout: Option<Duration>) {
+        self.stall_timeout = timeout;
+    }
+
+    /// Number of connected receivers and callbacks.
+    pub fn receiver_count(&self) -> usize {
+        self.senders.len()
+    }
 }
 
 impl<T: Clone> Emitter<T> {
     pub fn connect(&mut self, sender: Sender<T>) -> SlotToken {
+        self.connect_slot(Slot::Unbounded(sender))
+    }
+
+    pub fn connect_bounded(&mut self, sender: BoundedSender<T>) -> SlotToken {
+        self.connect_slot(Slot::Bounded(sender, None))
+    }
+
+    /// Invokes `callback` on the emitting thread for every value instead of
+    /// sending it through a channel. It stays connected until disconnected.
+    pub fn connect_callback(&mut self, callback: Callback<T>) -> SlotToken {
+        self.connect_slot(Slot::Callback(callback))
+    }
+
+    fn connect_slot(&mut self, slot: Slot<T>) -> SlotToken {
         static COUNTER: AtomicUsize = AtomicUsize::new(0);
         let id = SlotToken(COUNTER.fetch_add(1, Ordering::Relaxed));
-        self.senders.insert(id, sender);
+        self.senders.insert(id, slot);
+        id
+    }
+
+    pub fn connect_filtered(&mut self, sender: Sender<T>, filter: fn(&T) -> bool) -> SlotToken {
+        let id = self.connect(sender);
+        self.filters.insert(id, filter);
         id
     }
 
     pub fn disconnect(&mut self, id: &SlotToken) {
         self.senders.remove(id);
+        self.filters.remove(id);
+    }
+
+    pub fn new_receiver(&mut self) -> Receiver<T> {
+        let (sender, receiver) = channel();
+        self.connect(sender);
+        receiver
+    }
+
+    pub fn new_filtered_receiver(&mut self, filter: fn(&T) -> bool) -> Receiver<T> {
+        let (sender, receiver) = channel();
+        self.connect_filtered(sender, filter);
+        receiver
+    }
+
+    /// Returns a receiver holding at most `capacity` values, subject to the
+    /// emitter's `FullChannelPolicy` when it fills up.
+    pub fn new_bounded_receiver(&mut self, capacity: usize) -> BoundedReceiver<T> {
+        let (sender, receiver) = bounded(capacity);
+        self.connect_bounded(sender);
+        receiver
+    }
+
+    pub fn emit(&mut self, args: T) {
+        let filters = &self.filters;
+        let policy = self.full_channel_policy;
+        let stall_timeout = self.stall_timeout;
+        self.senders.retain(|id, slot| {
+            match filters.get(id) {
+                Some(filter) if !filter(&args) => true,
+                _ => match slot {
+                    Slot::Unbounded(sender) => sender.send(args.clone()).is_ok(),
+                    Slot::Bounded(sender, full_since) => {
+                        let mut policy = policy;
+                        if !sender.is_full() {
+                            *full_since = None;
+                        } else if let Some(timeout) = stall_timeout {
+                            if full_since.get_or_insert_with(Instant::now).elapsed() > timeout {
+                                return false;
+                            }
+                            if policy != FullChannelPolicy::DropOldest {
+                                policy = FullChannelPolicy::DropNewest;
+                            }
+                        }
+
+                        sender.send(args.clone(), policy)
+                    }
+                    Slot::Callback(callback) => {
+                        callback(&args);
+                        true
+                    }
+                },
+            }
+        });
+        let senders = &self.senders;
+        self.filters.retain(|id, _| senders.contains_key(id));
+    }
+}
+
+pub struct LatchingEmitter<T: Clone> {
+    emitter: Emitter<T>,
+    latest: Option<T>,
+}
+
+impl<T: Clone> LatchingEmitter<T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        LatchingEmitter {
+            emitter: Emitter::new(),
+            latest: None,
+        }
+    }
+
+    pub fn latest(&self) -> Option<T> {
+        self.latest.clone()
+    }
+
+    pub fn connect(&mut self, sender: Sender<T>) -> SlotToken {
+        if let Some(latest) = &self.latest {
+            // A send failure means the receiver is already gone; emit will prune it.
+            let _ = sender.send(latest.clone());
+        }
+        self.emitter.connect(sender)
+    }
+
+    pub fn disconnect(&mut self, id: &SlotToken) {
+        self.emitter.disconnect(id);
     }
 
     pub fn new_receiver(&mut self) -> Receiver<T> {
@@ -39,7 +183,7 @@ impl<T: Clone> Emitter<T> {
     }
 
     pub fn emit(&mut self, args: T) {
-        self.senders
-            .retain(|_, sender| sender.send(args.clone()).is_ok());
+        self.latest = Some(args.clone());
+        self.emitter.emit(args);
     }
-}
\ No newline at end of file
+}
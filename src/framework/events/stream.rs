@@ -0,0 +1,55 @@
+use crate::schema::notification::Notification;
+use crate::schema::value::RawValue;
+
+use std::sync::mpsc::{Receiver, TryIter};
+
+/// Iterator over a notification receiver. Iterating blocks for the next
+/// notification and ends once every sender is gone; `try_iter` drains what
+/// is already queued without blocking.
+pub struct NotificationStream {
+    receiver: Receiver<Notification>,
+}
+
+impl NotificationStream {
+    pub fn new(receiver: Receiver<Notification>) -> Self {
+        Self { receiver }
+    }
+
+    pub fn try_iter(&self) -> TryIter<'_, Notification> {
+        self.receiver.try_iter()
+    }
+
+    pub fn into_inner(self) -> Receiver<Notification> {
+        self.receiver
+    }
+}
+
+impl From<Receiver<Notification>> for NotificationStream {
+    fn from(receiver: Receiver<Notification>) -> Self {
+        Self::new(receiver)
+    }
+}
+
+impl Iterator for NotificationStream {
+    type Item = Notification;
+
+    fn next(&mut self) -> Option<Notification> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Combinators for any iterator of notifications, blocking or not.
+pub trait NotificationIterExt: Iterator<Item = Notification> + Sized {
+    /// Keeps notifications about the field named `name`.
+    fn filter_field(self, name: &str) -> impl Iterator<Item = Notification> {
+        let name = name.to_string();
+        self.filter(move |n| n.current.name() == name)
+    }
+
+    /// Maps each notification to a result computed from its current value.
+    fn map_value<R>(self, mut f: impl FnMut(RawValue) -> R) -> impl Iterator<Item = R> {
+        self.map(move |n| f(n.current.value().into_raw()))
+    }
+}
+
+impl<I: Iterator<Item = Notification>> NotificationIterExt for I {}
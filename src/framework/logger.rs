@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard};
 use crate::loggers::common::{LogLevel, LoggerTrait};
 
 pub type LoggerRef = Rc<RefCell<dyn LoggerTrait>>;
@@ -10,14 +11,41 @@ impl Logger {
         Logger(Rc::new(RefCell::new(logger)))
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Self {
         Logger(self.0.clone())
     }
 
+    /// Returns a logger writing through this one with every message
+    /// prefixed by `[scope]`.
+    pub fn scoped(&self, scope: &str) -> Logger {
+        Logger::new(Scoped {
+            inner: self.clone(),
+            scope: scope.to_string(),
+        })
+    }
+
     pub fn log(&self, level: &LogLevel, message: &str) {
         self.0.borrow_mut().log(level, message);
     }
 
+    /// Builds the message only if `level` passes the logger's filter. The
+    /// logger isn't borrowed while `f` runs, so `f` may log too.
+    pub fn log_lazy(&self, level: &LogLevel, f: impl FnOnce() -> String) {
+        let enabled = self.0.borrow().enabled(level);
+        if enabled {
+            self.log(level, &f());
+        }
+    }
+
+    pub fn trace_lazy(&self, f: impl FnOnce() -> String) {
+        self.log_lazy(&LogLevel::Trace, f);
+    }
+
+    pub fn debug_lazy(&self, f: impl FnOnce() -> String) {
+        self.log_lazy(&LogLevel::Debug, f);
+    }
+
     pub fn trace(&self, message: &str) {
         self.0.borrow_mut().trace(message);
     }
@@ -37,4 +65,143 @@ impl Logger {
     pub fn error(&self, message: &str) {
         self.0.borrow_mut().error(message);
     }
-}
\ No newline at end of file
+}
+
+struct Scoped {
+    inner: Logger,
+    scope: String,
+}
+
+impl LoggerTrait for Scoped {
+    fn log(&self, level: &LogLevel, message: &str) {
+        self.inner.log(level, &format!("[{}] {}", self.scope, message));
+    }
+
+    fn level(&self) -> LogLevel {
+        self.inner.0.borrow().level()
+    }
+}
+
+pub type SyncLoggerRef = Arc<Mutex<dyn LoggerTrait + Send>>;
+
+/// Thread-safe counterpart of `Logger` for use from spawned threads. Each
+/// message is written under a lock, so lines from different threads never
+/// interleave.
+pub struct SyncLogger(SyncLoggerRef);
+
+impl Clone for SyncLogger {
+    fn clone(&self) -> Self {
+        SyncLogger(self.0.clone())
+    }
+}
+
+impl SyncLogger {
+    pub fn new(logger: impl LoggerTrait + Send + 'static) -> Self {
+        SyncLogger(Arc::new(Mutex::new(logger)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, dyn LoggerTrait + Send + 'static> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    pub fn log(&self, level: &LogLevel, message: &str) {
+        self.lock().log(level, message);
+    }
+
+    /// Builds the message only if `level` passes the logger's filter. The
+    /// lock isn't held while `f` runs, so `f` may log too.
+    pub fn log_lazy(&self, level: &LogLevel, f: impl FnOnce() -> String) {
+        let enabled = self.lock().enabled(level);
+        if enabled {
+            self.log(level, &f());
+        }
+    }
+
+    pub fn trace_lazy(&self, f: impl FnOnce() -> String) {
+        self.log_lazy(&LogLevel::Trace, f);
+    }
+
+    pub fn debug_lazy(&self, f: impl FnOnce() -> String) {
+        self.log_lazy(&LogLevel::Debug, f);
+    }
+
+    pub fn trace(&self, message: &str) {
+        self.lock().trace(message);
+    }
+
+    pub fn debug(&self, message: &str) {
+        self.lock().debug(message);
+    }
+
+    pub fn info(&self, message: &str) {
+        self.lock().info(message);
+    }
+
+    pub fn warning(&self, message: &str) {
+        self.lock().warning(message);
+    }
+
+    pub fn error(&self, message: &str) {
+        self.lock().error(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Collect(Rc<RefCell<Vec<String>>>);
+
+    impl LoggerTrait for Collect {
+        fn log(&self, _level: &LogLevel, message: &str) {
+            self.0.borrow_mut().push(message.to_string());
+        }
+
+        fn level(&self) -> LogLevel {
+            LogLevel::Debug
+        }
+    }
+
+    #[test]
+    fn log_lazy_allows_logging_from_the_closure() {
+        let lines = Rc::new(RefCell::new(vec![]));
+        let logger = Logger::new(Collect(lines.clone()));
+
+        logger.debug_lazy(|| {
+            logger.info("inner");
+            "outer".to_string()
+        });
+        logger.trace_lazy(|| unreachable!("trace is filtered out"));
+
+        assert_eq!(*lines.borrow(), vec!["inner".to_string(), "outer".to_string()]);
+    }
+
+    struct SyncCollect(Arc<Mutex<Vec<String>>>);
+
+    impl LoggerTrait for SyncCollect {
+        fn log(&self, _level: &LogLevel, message: &str) {
+            self.0.lock().unwrap().push(message.to_string());
+        }
+
+        fn level(&self) -> LogLevel {
+            LogLevel::Debug
+        }
+    }
+
+    #[test]
+    fn sync_log_lazy_allows_logging_from_the_closure() {
+        let lines = Arc::new(Mutex::new(vec![]));
+        let logger = SyncLogger::new(SyncCollect(lines.clone()));
+
+        logger.debug_lazy(|| {
+            logger.info("inner");
+            "outer".to_string()
+        });
+        logger.trace_lazy(|| unreachable!("trace is filtered out"));
+
+        assert_eq!(*lines.lock().unwrap(), vec!["inner".to_string(), "outer".to_string()]);
+    }
+}
@@ -1,6 +1,8 @@
 use crate::error::Error;
 use crate::framework::client::Client;
-use crate::framework::events::emitter::Emitter;
+use crate::framework::events::bounded::{BoundedReceiver, FullChannelPolicy};
+use crate::framework::events::emitter::{Emitter, SlotToken};
+use crate::framework::logger::Logger;
 use crate::Result;
 use crate::schema::notification::{Notification, Config, Token};
 
@@ -8,21 +10,78 @@ use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Receives notifications directly instead of through a channel, e.g. to
+/// forward them elsewhere without an extra hop. Sinks run while the
+/// notification manager is busy processing, so they must not call back into
+/// the `Database` or `NotificationManager`.
+pub trait NotificationSink {
+    fn deliver(&mut self, notification: &Notification);
+}
+
+/// Identifies a sink connected by `register_sink`, to disconnect it again
+/// with `unregister_sink`. It stays valid across renewals and
+/// re-registrations, which change the registration's token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkHandle {
+    config: Config,
+    slot: SlotToken,
+}
+
+impl SinkHandle {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+}
+
+/// Counts of notifications seen by `process_notifications` since the
+/// manager was created or its stats were last reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotificationStats {
+    /// Fetched from the server.
+    pub received: u64,
+    /// Handed to the emitter of their registration.
+    pub emitted: u64,
+    /// Skipped because only their value's representation changed.
+    pub suppressed: u64,
+    /// Skipped because a newer write was already delivered, or because their
+    /// token no longer belongs to a registration (renewed or unregistered).
+    pub dropped: u64,
+}
 
 pub struct _NotificationManager {
     registered_config: HashSet<Config>,
     config_to_token: HashMap<Config, Token>,
+    token_to_config: HashMap<Token, Config>,
     token_to_callback_list: HashMap<Token, Emitter<Notification>>,
+    drop_stale: bool,
+    last_write_times: HashMap<(String, String), DateTime<Utc>>,
+    logger: Option<Logger>,
+    preserve_on_clear: bool,
+    pending_reregistration: HashMap<Config, Emitter<Notification>>,
+    fetch_timeout: Option<Duration>,
+    all_notifications: Emitter<Notification>,
+    suppress_loosely_unchanged: bool,
+    longpoll_wait: Option<Duration>,
+    token_expiry: HashMap<Token, DateTime<Utc>>,
+    dead_consumer_timeout: Option<Duration>,
+    full_channel_policy: FullChannelPolicy,
+    stats: NotificationStats,
 }
 
 type NotificationManagerRef = Rc<RefCell<_NotificationManager>>;
 pub struct NotificationManager(NotificationManagerRef);
 
 impl NotificationManager {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         NotificationManager(Rc::new(RefCell::new(_NotificationManager::new())))
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Self {
         NotificationManager(self.0.clone())
     }
@@ -31,6 +90,101 @@ impl NotificationManager {
         self.0.borrow_mut().clear();
     }
 
+    /// Forgets every registration and drops all emitters, including the one
+    /// behind `subscribe_all`, so every receiver handed out disconnects.
+    /// Server-side registrations are left to expire with the client.
+    pub fn close_all(&self) {
+        self.0.borrow_mut().close_all();
+    }
+
+    pub fn set_logger(&self, logger: Logger) {
+        self.0.borrow_mut().logger = Some(logger);
+    }
+
+    pub fn stats(&self) -> NotificationStats {
+        self.0.borrow().stats
+    }
+
+    pub fn reset_stats(&self) {
+        self.0.borrow_mut().stats = NotificationStats::default();
+    }
+
+    /// When enabled, notifications whose current write time is older than the
+    /// last one delivered for the same entity field are dropped.
+    pub fn set_drop_stale(&self, drop_stale: bool) {
+        let mut manager = self.0.borrow_mut();
+        manager.drop_stale = drop_stale;
+        manager.last_write_times.clear();
+    }
+
+    /// When enabled, `clear` keeps each registration's config and receivers
+    /// so that `reregister` can restore them with fresh server tokens after a
+    /// reconnect. Existing receivers stay valid throughout.
+    pub fn set_preserve_on_clear(&self, preserve: bool) {
+        self.0.borrow_mut().preserve_on_clear = preserve;
+    }
+
+    pub fn reregister(&self, client: Client) -> Result<usize> {
+        self.0.borrow_mut().reregister(client)
+    }
+
+    /// Registers again every registration whose server lease expires within
+    /// `within`, moving its receivers over to the new token, and returns how
+    /// many were renewed. Call it periodically during long sessions. A failed
+    /// renewal doesn't stop the others; the error then reports how many were
+    /// renewed and why the rest failed.
+    pub fn renew_expiring(&self, client: Client, within: Duration) -> Result<usize> {
+        self.0.borrow_mut().renew_expiring(client, within)
+    }
+
+    pub fn export_configs(&self) -> Vec<Config> {
+        self.0.borrow().export_configs()
+    }
+
+    pub fn token_for(&self, config: &Config) -> Option<Token> {
+        self.0.borrow().config_to_token.get(config).cloned()
+    }
+
+    /// When enabled, notifications for `notify_on_change` registrations are
+    /// dropped if the value is unchanged under `RawValue::loosely_eq`, e.g.
+    /// when it only switched between `Integer(1)` and `Float(1.0)`.
+    pub fn set_suppress_loosely_unchanged(&self, suppress: bool) {
+        self.0.borrow_mut().suppress_loosely_unchanged = suppress;
+    }
+
+    /// Bounds how long `process_notifications` may wait on the client by
+    /// applying `timeout` as the client's request timeout while fetching.
+    /// A fetch that runs over fails with `Error::Timeout`.
+    pub fn set_fetch_timeout(&self, timeout: Option<Duration>) {
+        self.0.borrow_mut().fetch_timeout = timeout;
+    }
+
+    /// Fetches notifications by long-polling, letting the server hold each
+    /// fetch for up to `wait` until notifications arrive. This blocks the
+    /// caller for that long when nothing happens. If the client does not
+    /// support long-polling, regular polling is used from then on.
+    pub fn set_longpoll_wait(&self, wait: Option<Duration>) {
+        self.0.borrow_mut().longpoll_wait = wait;
+    }
+
+    /// Evicts bounded receivers (see `register_bounded`) that stay full for
+    /// longer than `timeout` and unregisters any registration left without
+    /// receivers, logging each eviction. Once set, the timeout alone decides
+    /// eviction; the full-channel policy only picks what is dropped until
+    /// then. Unbounded receivers never fill up, so only those from dropped
+    /// consumers are cleaned up. Disabled by default.
+    pub fn set_dead_consumer_timeout(&self, timeout: Option<Duration>) {
+        self.0.borrow_mut().set_dead_consumer_timeout(timeout);
+    }
+
+    /// Chooses what happens to bounded receivers that are full when a
+    /// notification arrives. Defaults to `DisconnectSlow`. `Block` is
+    /// refused: notifications can't leave the thread processing them, so
+    /// blocking would wait on a consumer that can never run.
+    pub fn set_full_channel_policy(&self, policy: FullChannelPolicy) -> Result<()> {
+        self.0.borrow_mut().set_full_channel_policy(policy)
+    }
+
     pub fn register(
         &self,
         client: Client,
@@ -39,30 +193,239 @@ impl NotificationManager {
         self.0.borrow_mut().register(client, config)
     }
 
+    /// Registers like `register`, but the receiver holds at most `capacity`
+    /// notifications, subject to the full-channel policy beyond that.
+    pub fn register_bounded(
+        &self,
+        client: Client,
+        config: &Config,
+        capacity: usize,
+    ) -> Result<BoundedReceiver<Notification>> {
+        self.0.borrow_mut().register_bounded(client, config, capacity)
+    }
+
+    /// Registers `config.with_context(context)` and only delivers the
+    /// notifications for which `filter` returns true. `context` names the
+    /// context fields the filter inspects, so the server includes them.
+    pub fn register_filtered(
+        &self,
+        client: Client,
+        config: &Config,
+        context: &[&str],
+        filter: fn(&Notification) -> bool,
+    ) -> Result<Receiver<Notification>> {
+        self.0.borrow_mut().register_filtered(client, config, context, filter)
+    }
+
+    pub fn register_sink(
+        &self,
+        client: Client,
+        config: &Config,
+        sink: Box<dyn NotificationSink>,
+    ) -> Result<SinkHandle> {
+        self.0.borrow_mut().register_sink(client, config, sink)
+    }
+
+    /// Disconnects the sink behind `handle`, unregistering its registration
+    /// if nothing else receives from it anymore.
+    pub fn unregister_sink(&self, client: Client, handle: &SinkHandle) -> Result<()> {
+        self.0.borrow_mut().unregister_sink(client, handle)
+    }
+
     pub fn unregister(&self, client: Client, token: &Token) -> Result<()> {
         self.0.borrow_mut().unregister(client, token)
     }
 
+    /// Unregisters every registration for `entity_type` and returns how many
+    /// there were. A failed unregistration doesn't stop the others; the
+    /// error then reports how many succeeded and why the rest failed.
+    pub fn unregister_by_type(&self, client: Client, entity_type: &str) -> Result<usize> {
+        self.0.borrow_mut().unregister_by_type(client, entity_type)
+    }
+
+    /// Returns a receiver that gets a copy of every notification processed,
+    /// regardless of which registration it belongs to.
+    pub fn subscribe_all(&self) -> Receiver<Notification> {
+        self.0.borrow_mut().all_notifications.new_receiver()
+    }
+
     pub fn process_notifications(&self, client: Client) -> Result<()> {
         self.0.borrow_mut().process_notifications(client)
     }
+
+    /// Processes notifications once more, fetching with `timeout` and
+    /// without long-polling, for a final delivery before shutdown.
+    pub fn drain(&self, client: Client, timeout: Duration) -> Result<()> {
+        self.0.borrow_mut().drain(client, timeout)
+    }
 }
 
 impl _NotificationManager {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         _NotificationManager {
             registered_config: HashSet::new(),
             config_to_token: HashMap::new(),
+            token_to_config: HashMap::new(),
             token_to_callback_list: HashMap::new(),
+            drop_stale: false,
+            last_write_times: HashMap::new(),
+            logger: None,
+            preserve_on_clear: false,
+            pending_reregistration: HashMap::new(),
+            fetch_timeout: None,
+            all_notifications: Emitter::new(),
+            suppress_loosely_unchanged: false,
+            longpoll_wait: None,
+            token_expiry: HashMap::new(),
+            dead_consumer_timeout: None,
+            full_channel_policy: FullChannelPolicy::DisconnectSlow,
+            stats: NotificationStats::default(),
         }
     }
 }
 
 impl _NotificationManager {
     fn clear(&mut self) {
+        if self.preserve_on_clear {
+            for (config, token) in self.config_to_token.drain() {
+                if let Some(emitter) = self.token_to_callback_list.remove(&token) {
+                    self.pending_reregistration.insert(config, emitter);
+                }
+            }
+        } else {
+            self.pending_reregistration.clear();
+        }
+
+        self.registered_config.clear();
+        self.config_to_token.clear();
+        self.token_to_config.clear();
+        self.token_to_callback_list.clear();
+        self.token_expiry.clear();
+        self.last_write_times.clear();
+    }
+
+    fn close_all(&mut self) {
         self.registered_config.clear();
         self.config_to_token.clear();
+        self.token_to_config.clear();
         self.token_to_callback_list.clear();
+        self.pending_reregistration.clear();
+        self.token_expiry.clear();
+        self.last_write_times.clear();
+        self.all_notifications = Emitter::new();
+    }
+
+    fn reregister(&mut self, client: Client) -> Result<usize> {
+        let configs: Vec<Config> = self.pending_reregistration.keys().cloned().collect();
+
+        for config in &configs {
+            self.emitter_for(client.clone(), config)?;
+        }
+
+        Ok(configs.len())
+    }
+
+    fn renew_expiring(&mut self, client: Client, within: Duration) -> Result<usize> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "renew_expiring");
+        let deadline = Utc::now()
+            .checked_add_signed(TimeDelta::from_std(within)?)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+        let expiring: Vec<(Config, Token)> = self
+            .config_to_token
+            .iter()
+            .filter(|(_, token)| {
+                self.token_expiry
+                    .get(*token)
+                    .is_some_and(|expires_at| *expires_at <= deadline)
+            })
+            .map(|(config, token)| (config.clone(), token.clone()))
+            .collect();
+
+        let mut failures = vec![];
+        for (config, old_token) in &expiring {
+            let lease = match client.register_notification_leased(config) {
+                Ok(lease) => lease,
+                Err(e) => {
+                    failures.push(format!(
+                        "field '{}' of entity '{}': {}",
+                        config.field, config.entity_id, e
+                    ));
+                    continue;
+                }
+            };
+
+            if let Some(emitter) = self.token_to_callback_list.remove(old_token) {
+                self.token_to_callback_list.insert(lease.token.clone(), emitter);
+            }
+            self.token_expiry.remove(old_token);
+            if let Some(expires_at) = lease.expires_at {
+                self.token_expiry.insert(lease.token.clone(), expires_at);
+            }
+            self.token_to_config.remove(old_token);
+            self.token_to_config.insert(lease.token.clone(), config.clone());
+            self.config_to_token.insert(config.clone(), lease.token);
+
+            if let Err(e) = client.unregister_notification(old_token) {
+                if let Some(logger) = &self.logger {
+                    logger.debug(&format!(
+                        "[{}] Failed to release renewed registration: {}",
+                        c, e
+                    ));
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(Error::from_notification(&format!(
+                "Renewed {} of {} expiring notification(s), the rest failed: {}",
+                expiring.len() - failures.len(),
+                expiring.len(),
+                failures.join("; ")
+            )));
+        }
+
+        Ok(expiring.len())
+    }
+
+    fn export_configs(&self) -> Vec<Config> {
+        self.registered_config
+            .iter()
+            .chain(self.pending_reregistration.keys())
+            .cloned()
+            .collect()
+    }
+
+    fn set_dead_consumer_timeout(&mut self, timeout: Option<Duration>) {
+        self.dead_consumer_timeout = timeout;
+
+        for emitter in self
+            .token_to_callback_list
+            .values_mut()
+            .chain(self.pending_reregistration.values_mut())
+        {
+            emitter.set_stall_timeout(timeout);
+        }
+    }
+
+    fn set_full_channel_policy(&mut self, policy: FullChannelPolicy) -> Result<()> {
+        if policy == FullChannelPolicy::Block {
+            return Err(Error::from_unsupported(
+                "Notification receivers can't block, since they are drained on the thread that fills them",
+            ));
+        }
+
+        self.full_channel_policy = policy;
+        for emitter in self
+            .token_to_callback_list
+            .values_mut()
+            .chain(self.pending_reregistration.values_mut())
+        {
+            emitter.set_full_channel_policy(policy);
+        }
+
+        Ok(())
     }
 
     fn register(
@@ -70,41 +433,114 @@ impl _NotificationManager {
         client: Client,
         config: &Config,
     ) -> Result<Receiver<Notification>> {
-        if self.registered_config.contains(&config) {
-            let token = self
-                .config_to_token
-                .get(config)
-                .ok_or(Error::from_notification(
-                    "Inconsistent notification state during registration",
-                ))?;
+        Ok(self.emitter_for(client, config)?.new_receiver())
+    }
 
-            let receiver = self
-                .token_to_callback_list
-                .get_mut(token)
-                .ok_or(Error::from_notification(
-                    "Inconsistent notification state during registration",
-                ))?
-                .new_receiver();
+    fn register_bounded(
+        &mut self,
+        client: Client,
+        config: &Config,
+        capacity: usize,
+    ) -> Result<BoundedReceiver<Notification>> {
+        Ok(self.emitter_for(client, config)?.new_bounded_receiver(capacity))
+    }
 
-            return Ok(receiver);
-        }
+    fn register_filtered(
+        &mut self,
+        client: Client,
+        config: &Config,
+        context: &[&str],
+        filter: fn(&Notification) -> bool,
+    ) -> Result<Receiver<Notification>> {
+        let config = config.with_context(context);
+        Ok(self.emitter_for(client, &config)?.new_filtered_receiver(filter))
+    }
 
-        let token = client.register_notification(config)?;
+    fn register_sink(
+        &mut self,
+        client: Client,
+        config: &Config,
+        mut sink: Box<dyn NotificationSink>,
+    ) -> Result<SinkHandle> {
+        let slot = self
+            .emitter_for(client, config)?
+            .connect_callback(Box::new(move |notification| sink.deliver(notification)));
 
-        self.registered_config.insert(config.clone());
-        self.config_to_token.insert(config.clone(), token.clone());
-        self.token_to_callback_list
-            .insert(token.clone(), Emitter::new());
+        Ok(SinkHandle {
+            config: config.clone(),
+            slot,
+        })
+    }
+
+    fn unregister_sink(&mut self, client: Client, handle: &SinkHandle) -> Result<()> {
+        if let Some(emitter) = self.pending_reregistration.get_mut(&handle.config) {
+            emitter.disconnect(&handle.slot);
+            if emitter.receiver_count() == 0 {
+                self.pending_reregistration.remove(&handle.config);
+            }
+            return Ok(());
+        }
+
+        let token = self
+            .config_to_token
+            .get(&handle.config)
+            .cloned()
+            .ok_or(Error::from_notification(
+                "Sink not found during unregistration",
+            ))?;
 
-        let receiver = self
+        let emitter = self
             .token_to_callback_list
             .get_mut(&token)
+            .ok_or(Error::from_notification(
+                "Inconsistent notification state during unregistration",
+            ))?;
+
+        emitter.disconnect(&handle.slot);
+        if emitter.receiver_count() == 0 {
+            self.unregister(client, &token)?;
+        }
+
+        Ok(())
+    }
+
+    fn emitter_for(
+        &mut self,
+        client: Client,
+        config: &Config,
+    ) -> Result<&mut Emitter<Notification>> {
+        if !self.registered_config.contains(config) {
+            let lease = client.register_notification_leased(config)?;
+            let token = lease.token;
+            if let Some(expires_at) = lease.expires_at {
+                self.token_expiry.insert(token.clone(), expires_at);
+            }
+
+            self.registered_config.insert(config.clone());
+            self.config_to_token.insert(config.clone(), token.clone());
+            self.token_to_config.insert(token.clone(), config.clone());
+            let mut emitter = self
+                .pending_reregistration
+                .remove(config)
+                .unwrap_or_else(Emitter::new);
+            emitter.set_stall_timeout(self.dead_consumer_timeout);
+            emitter.set_full_channel_policy(self.full_channel_policy);
+            self.token_to_callback_list.insert(token, emitter);
+        }
+
+        let token = self
+            .config_to_token
+            .get(config)
             .ok_or(Error::from_notification(
                 "Inconsistent notification state during registration",
             ))?
-            .new_receiver();
+            .clone();
 
-        Ok(receiver)
+        self.token_to_callback_list
+            .get_mut(&token)
+            .ok_or(Error::from_notification(
+                "Inconsistent notification state during registration",
+            ))
     }
 
     fn unregister(&mut self, client: Client, token: &Token) -> Result<()> {
@@ -117,25 +553,199 @@ impl _NotificationManager {
         client.unregister_notification(token)?;
 
         self.token_to_callback_list.remove(token);
-        self.config_to_token.retain(|_, v| v != token);
-        self.registered_config
-            .retain(|c| self.config_to_token.contains_key(c));
+        self.token_expiry.remove(token);
+        if let Some(config) = self.token_to_config.remove(token) {
+            self.config_to_token.remove(&config);
+            self.registered_config.remove(&config);
+        }
 
         Ok(())
     }
 
+    fn unregister_by_type(&mut self, client: Client, entity_type: &str) -> Result<usize> {
+        let tokens: Vec<Token> = self
+            .config_to_token
+            .iter()
+            .filter(|(config, _)| config.entity_type == entity_type)
+            .map(|(_, token)| token.clone())
+            .collect();
+
+        let mut failures = vec![];
+        for token in &tokens {
+            if let Err(e) = self.unregister(client.clone(), token) {
+                failures.push(e.to_string());
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(Error::from_notification(&format!(
+                "Unregistered {} of {} notification(s) of type '{}', the rest failed: {}",
+                tokens.len() - failures.len(),
+                tokens.len(),
+                entity_type,
+                failures.join("; ")
+            )));
+        }
+
+        Ok(tokens.len())
+    }
+
+    fn is_stale(&mut self, notification: &Notification) -> bool {
+        let key = (notification.current.entity_id(), notification.current.name());
+        let write_time = notification.current.write_time();
+
+        match self.last_write_times.get(&key) {
+            Some(last) if write_time < *last => true,
+            _ => {
+                self.last_write_times.insert(key, write_time);
+                false
+            }
+        }
+    }
+
+    fn get_notifications(&mut self, client: &Client) -> Result<Vec<Notification>> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "get_notifications");
+
+        let Some(wait) = self.longpoll_wait else {
+            return client.get_notifications();
+        };
+
+        match client.get_notifications_longpoll(wait) {
+            Err(e) if Error::is_unsupported(e.as_ref()) => {
+                if let Some(logger) = &self.logger {
+                    logger.warning(&format!(
+                        "[{}] Falling back to polling for notifications: {}",
+                        c, e
+                    ));
+                }
+                self.longpoll_wait = None;
+                client.get_notifications()
+            }
+            result => result,
+        }
+    }
+
+    fn fetch_notifications(&mut self, client: Client) -> Result<Vec<Notification>> {
+        let Some(timeout) = self.fetch_timeout else {
+            return self.get_notifications(&client);
+        };
+
+        let previous = client.timeout();
+        let start = Instant::now();
+        client.set_timeout(Some(timeout));
+        let result = self.get_notifications(&client);
+        client.set_timeout(previous);
+
+        match result {
+            Err(e) if start.elapsed() >= timeout => Err(Error::from_timeout(&format!(
+                "Fetching notifications took longer than {:?}: {}",
+                timeout, e
+            ))),
+            result => result,
+        }
+    }
+
+    fn is_loosely_unchanged(&self, notification: &Notification) -> bool {
+        let token = Token::from(notification.token.clone());
+        let notify_on_change = self
+            .token_to_config
+            .get(&token)
+            .is_some_and(|config| config.notify_on_change);
+
+        notify_on_change
+            && notification
+                .current
+                .value()
+                .into_raw()
+                .loosely_eq(&notification.previous.value().into_raw())
+    }
+
+    fn drain(&mut self, client: Client, timeout: Duration) -> Result<()> {
+        let fetch_timeout = self.fetch_timeout.replace(timeout);
+        let longpoll_wait = self.longpoll_wait.take();
+        let result = self.process_notifications(client);
+        self.fetch_timeout = fetch_timeout;
+        self.longpoll_wait = longpoll_wait;
+        result
+    }
+
     fn process_notifications(&mut self, client: Client) -> Result<()> {
-        let notifications = client.get_notifications()?;
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "process_notifications");
+        let notifications = self.fetch_notifications(client.clone())?;
+        let mut abandoned: Vec<Token> = vec![];
+        self.stats.received += notifications.len() as u64;
 
         for notification in &notifications {
             let token = Token::from(notification.token.clone());
-            let emitter =
-                self.token_to_callback_list
-                    .get_mut(&token)
-                    .ok_or(Error::from_notification(
-                        "Cannot process notification: Callback list doesn't exist for token",
-                    ))?;
+            if !self.token_to_callback_list.contains_key(&token) {
+                self.stats.dropped += 1;
+                if let Some(logger) = &self.logger {
+                    logger.debug(&format!(
+                        "[{}] Dropped notification for field '{}' of entity '{}' under unknown token '{}'",
+                        c,
+                        notification.current.name(),
+                        notification.current.entity_id(),
+                        notification.token
+                    ));
+                }
+                continue;
+            }
+
+            if self.drop_stale && self.is_stale(notification) {
+                self.stats.dropped += 1;
+                if let Some(logger) = &self.logger {
+                    logger.debug(&format!(
+                        "[{}] Dropped stale notification for field '{}' of entity '{}' written at {}",
+                        c,
+                        notification.current.name(),
+                        notification.current.entity_id(),
+                        notification.current.write_time()
+                    ));
+                }
+                continue;
+            }
+
+            if self.suppress_loosely_unchanged && self.is_loosely_unchanged(notification) {
+                self.stats.suppressed += 1;
+                continue;
+            }
+
+            self.all_notifications.emit(notification.clone());
+
+            let Some(emitter) = self.token_to_callback_list.get_mut(&token) else {
+                continue;
+            };
+
+            let receivers = emitter.receiver_count();
             emitter.emit(notification.clone());
+            self.stats.emitted += 1;
+
+            if self.dead_consumer_timeout.is_some() && emitter.receiver_count() < receivers {
+                if let Some(logger) = &self.logger {
+                    logger.warning(&format!(
+                        "[{}] Evicted {} unresponsive receiver(s) of notifications for field '{}' of entity '{}'",
+                        c,
+                        receivers - emitter.receiver_count(),
+                        notification.current.name(),
+                        notification.current.entity_id()
+                    ));
+                }
+
+                if emitter.receiver_count() == 0 && !abandoned.contains(&token) {
+                    abandoned.push(token);
+                }
+            }
+        }
+
+        for token in &abandoned {
+            if let Err(e) = self.unregister(client.clone(), token) {
+                if let Some(logger) = &self.logger {
+                    logger.warning(&format!(
+                        "[{}] Failed to unregister notification without receivers: {}",
+                        c, e
+                    ));
+                }
+            }
         }
 
         Ok(())
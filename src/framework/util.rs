@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+/// Rate-limits a periodic action: `should_run` returns true at most once per
+/// interval, and always on the first call or after a `reset`.
+pub struct Throttle {
+    interval: Duration,
+    last_run: Option<Instant>,
+}
+
+impl Throttle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_run: None,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    pub fn should_run(&mut self) -> bool {
+        self.should_run_at(Instant::now())
+    }
+
+    /// Like `should_run`, but taking the current time from the caller, which
+    /// keeps the decision deterministic for callers with their own clock.
+    pub fn should_run_at(&mut self, now: Instant) -> bool {
+        let due = self
+            .last_run
+            .is_none_or(|t| now.saturating_duration_since(t) >= self.interval);
+
+        if due {
+            self.last_run = Some(now);
+        }
+
+        due
+    }
+
+    /// Makes the next `should_run` return true.
+    pub fn reset(&mut self) {
+        self.last_run = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_run_at_runs_once_per_interval() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut throttle = Throttle::new(Duration::from_millis(100));
+
+        assert!(throttle.should_run_at(at(0)));
+        assert!(!throttle.should_run_at(at(50)));
+        assert!(!throttle.should_run_at(at(99)));
+        assert!(throttle.should_run_at(at(100)));
+        // The interval counts from the last run, not from the schedule
+        assert!(!throttle.should_run_at(at(150)));
+        assert!(throttle.should_run_at(at(250)));
+    }
+
+    #[test]
+    fn should_run_at_after_reset_and_with_earlier_times() {
+        let start = Instant::now() + Duration::from_secs(1);
+        let mut throttle = Throttle::new(Duration::from_secs(60));
+
+        assert!(throttle.should_run_at(start));
+        assert!(!throttle.should_run_at(start - Duration::from_millis(500)));
+
+        throttle.reset();
+        assert!(throttle.should_run_at(start + Duration::from_millis(1)));
+
+        throttle.set_interval(Duration::ZERO);
+        assert!(throttle.should_run_at(start + Duration::from_millis(1)));
+    }
+}
@@ -1,2 +1,6 @@
 pub mod common;
-pub mod database;
\ No newline at end of file
+pub mod database;
+pub mod demux;
+pub mod enrich;
+pub mod mirror;
+pub mod poll;
\ No newline at end of file
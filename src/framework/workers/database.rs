@@ -1,13 +1,16 @@
+use crate::error::Error;
 use crate::framework::application::Context;
 use crate::framework::workers::common::WorkerTrait;
-use crate::framework::events::emitter::Emitter;
+use crate::framework::events::emitter::LatchingEmitter;
+use crate::framework::util::Throttle;
 
 use crate::Result;
 
 use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
 pub struct Emitters {
-    pub connection_status: Emitter<bool>,
+    pub connection_status: LatchingEmitter<bool>,
 }
 
 pub struct Receivers {
@@ -17,23 +20,128 @@ pub struct Receivers {
 pub struct Worker {
     is_db_connected: bool,
     is_nw_connected: bool,
+    connect_log: Throttle,
+    notification_poll_interval: Option<Duration>,
+    next_notification_poll: Option<Instant>,
+    ctx: Option<Context>,
+    backlog_threshold: Option<usize>,
+    backlog_check: Throttle,
+    shutdown_drain_timeout: Option<Duration>,
     pub emitters: Emitters,
     pub receivers: Receivers,
 }
 
 impl Worker {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         Self {
             is_db_connected: false,
             is_nw_connected: false,
+            connect_log: Throttle::new(Duration::from_secs(10)),
+            notification_poll_interval: None,
+            next_notification_poll: None,
+            ctx: None,
+            backlog_threshold: None,
+            backlog_check: Throttle::new(Duration::from_secs(10)),
+            shutdown_drain_timeout: Some(Duration::from_secs(1)),
             emitters: Emitters {
-                connection_status: Emitter::new(),
+                connection_status: LatchingEmitter::new(),
             },
             receivers: Receivers {
                 network_connection_status: None,
             },
         }
     }
+
+    pub fn set_connect_log_interval(&mut self, interval: Duration) {
+        self.connect_log.set_interval(interval);
+    }
+
+    /// Polls notifications on their own schedule instead of once per tick.
+    /// Besides the worker's own tick, polls may also happen while the
+    /// application processes events between other workers, which allows
+    /// latencies below the loop interval. `None` polls every tick.
+    pub fn set_notification_poll_interval(&mut self, interval: Option<Duration>) {
+        self.notification_poll_interval = interval;
+        self.next_notification_poll = None;
+    }
+
+    /// Bounds the final notification drain done on deinitialize, while still
+    /// connected, before notification channels are closed. `None` skips the
+    /// drain. Defaults to one second.
+    pub fn set_shutdown_drain_timeout(&mut self, timeout: Option<Duration>) {
+        self.shutdown_drain_timeout = timeout;
+    }
+
+    fn poll_notifications(&mut self, ctx: &Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "poll_notifications");
+
+        if let Some(interval) = self.notification_poll_interval {
+            let now = Instant::now();
+            if self.next_notification_poll.is_some_and(|due| now < due) {
+                return Ok(());
+            }
+
+            self.next_notification_poll = Some(now + interval);
+        }
+
+        if let Err(e) = ctx.database().process_notifications() {
+            if !Error::is_timeout(e.as_ref()) {
+                return Err(e);
+            }
+
+            ctx.logger().warning(
+                format!("[{}] Notification processing stalled: {}", c, e).as_str(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Warns when the server reports more than `threshold` notifications
+    /// queued for this client, checking at most once per `interval`. The
+    /// check turns itself off if the server cannot report its backlog.
+    pub fn set_notification_backlog_threshold(&mut self, threshold: Option<usize>, interval: Duration) {
+        self.backlog_threshold = threshold;
+        self.backlog_check.set_interval(interval);
+        self.backlog_check.reset();
+    }
+
+    fn check_notification_backlog(&mut self, ctx: &Context) {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "check_notification_backlog");
+
+        let Some(threshold) = self.backlog_threshold else {
+            return;
+        };
+
+        if !self.backlog_check.should_run() {
+            return;
+        }
+
+        match ctx.database().notification_backlog() {
+            Ok(backlog) if backlog > threshold => {
+                ctx.logger().warning(
+                    format!(
+                        "[{}] Notification backlog of {} exceeds threshold of {}",
+                        c, backlog, threshold
+                    )
+                    .as_str(),
+                );
+            }
+            Ok(_) => {}
+            Err(e) if Error::is_unsupported(e.as_ref()) => {
+                ctx.logger().debug(
+                    format!("[{}] Disabling backlog checks: {}", c, e).as_str(),
+                );
+                self.backlog_threshold = None;
+            }
+            Err(e) => {
+                ctx.logger().debug(
+                    format!("[{}] Failed to read notification backlog: {}", c, e).as_str(),
+                );
+            }
+        }
+    }
 }
 
 impl WorkerTrait for Worker {
@@ -43,6 +151,7 @@ impl WorkerTrait for Worker {
         ctx.logger().info(
             format!("[{}] Initializing database worker", c).as_str(),
         );
+        self.ctx = Some(ctx);
         Ok(())
     }
 
@@ -55,6 +164,7 @@ impl WorkerTrait for Worker {
                     format!("[{}] Network connection loss has disrupted database connection", c).as_str()
                 );
                 self.is_db_connected = false;
+                self.connect_log.reset();
                 self.emitters.connection_status.emit(self.is_db_connected);
             }
 
@@ -68,12 +178,15 @@ impl WorkerTrait for Worker {
                 );
                 ctx.database().clear_notifications();
                 self.is_db_connected = false;
+                self.connect_log.reset();
                 self.emitters.connection_status.emit(self.is_db_connected);
             }
 
-            ctx.logger().debug(
-                format!("[{}] Attempting to connect to the database...", c).as_str(),
-            );
+            if self.connect_log.should_run() {
+                ctx.logger().debug(
+                    format!("[{}] Attempting to connect to the database...", c).as_str(),
+                );
+            }
 
             ctx.database().disconnect();
             ctx.database().connect()?;
@@ -83,13 +196,30 @@ impl WorkerTrait for Worker {
                     format!("[{}] Connected to the database", c).as_str(),
                 );
                 self.is_db_connected = true;
+                self.connect_log.reset();
+
+                match ctx.database().reregister_notifications() {
+                    Ok(0) => {}
+                    Ok(count) => {
+                        ctx.logger().info(
+                            format!("[{}] Re-registered {} notification(s)", c, count).as_str(),
+                        );
+                    }
+                    Err(e) => {
+                        ctx.logger().error(
+                            format!("[{}] Failed to re-register notifications: {}", c, e).as_str(),
+                        );
+                    }
+                }
+
                 self.emitters.connection_status.emit(self.is_db_connected);
             }
 
             return Ok(());
         }
 
-        ctx.database().process_notifications()?;
+        self.poll_notifications(&ctx)?;
+        self.check_notification_backlog(&ctx);
 
         Ok(())
     }
@@ -100,6 +230,20 @@ impl WorkerTrait for Worker {
         ctx.logger().info(
             format!("[{}] Deinitializing database worker", c).as_str(),
         );
+
+        if let Some(timeout) = self.shutdown_drain_timeout {
+            if self.is_db_connected {
+                if let Err(e) = ctx.database().drain_notifications(timeout) {
+                    ctx.logger().warning(&format!(
+                        "[{}] Failed to drain notifications before shutdown: {}",
+                        c, e
+                    ));
+                }
+            }
+        }
+
+        ctx.database().close_notifications();
+        self.ctx = None;
         Ok(())
     }
 
@@ -110,6 +254,12 @@ impl WorkerTrait for Worker {
             }
         }
 
+        if self.notification_poll_interval.is_some() && self.is_nw_connected && self.is_db_connected {
+            if let Some(ctx) = self.ctx.clone() {
+                self.poll_notifications(&ctx)?;
+            }
+        }
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,79 @@
+use crate::framework::application::Context;
+use crate::framework::workers::common::WorkerTrait;
+use crate::framework::events::emitter::Emitter;
+use crate::schema::notification::Notification;
+
+use crate::Result;
+
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+
+pub struct Receivers {
+    pub notifications: Vec<Receiver<Notification>>,
+}
+
+/// Splits broadly registered notifications, such as those from
+/// `Database::register_type_notification`, into per-entity receivers.
+/// Notifications for entities nobody asked for are dropped.
+pub struct Worker {
+    entities: HashMap<String, Emitter<Notification>>,
+    pub receivers: Receivers,
+}
+
+impl Worker {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            entities: HashMap::new(),
+            receivers: Receivers {
+                notifications: vec![],
+            },
+        }
+    }
+
+    pub fn by_entity(&mut self, entity_id: &str) -> Receiver<Notification> {
+        self.entities
+            .entry(entity_id.to_string())
+            .or_insert_with(Emitter::new)
+            .new_receiver()
+    }
+}
+
+impl WorkerTrait for Worker {
+    fn intialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "initialize");
+
+        ctx.logger().info(
+            format!("[{}] Initializing demux worker", c).as_str(),
+        );
+        Ok(())
+    }
+
+    fn do_work(&mut self, _ctx: Context) -> Result<()> {
+        Ok(())
+    }
+
+    fn deinitialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "deinitialize");
+
+        ctx.logger().info(
+            format!("[{}] Deinitializing demux worker", c).as_str(),
+        );
+        Ok(())
+    }
+
+    fn process_events(&mut self) -> Result<()> {
+        for receiver in &self.receivers.notifications {
+            while let Ok(notification) = receiver.try_recv() {
+                if let Some(emitter) = self.entities.get_mut(&notification.current.entity_id()) {
+                    emitter.emit(notification);
+                }
+            }
+        }
+
+        // Forget entities whose receivers have all been dropped
+        self.entities.retain(|_, emitter| emitter.receiver_count() > 0);
+
+        Ok(())
+    }
+}
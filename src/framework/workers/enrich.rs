@@ -0,0 +1,125 @@
+use crate::framework::application::Context;
+use crate::framework::workers::common::WorkerTrait;
+use crate::framework::events::emitter::Emitter;
+use crate::schema::notification::Notification;
+
+use crate::Result;
+
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+
+#[derive(Clone)]
+pub struct EnrichedNotification {
+    pub name: String,
+    pub notification: Notification,
+}
+
+pub struct Emitters {
+    pub enriched: Emitter<EnrichedNotification>,
+}
+
+pub struct Receivers {
+    pub notifications: Vec<Receiver<Notification>>,
+}
+
+pub struct Worker {
+    ctx: Option<Context>,
+    entity_names: HashMap<String, String>,
+    pub emitters: Emitters,
+    pub receivers: Receivers,
+}
+
+impl Worker {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            ctx: None,
+            entity_names: HashMap::new(),
+            emitters: Emitters {
+                enriched: Emitter::new(),
+            },
+            receivers: Receivers {
+                notifications: vec![],
+            },
+        }
+    }
+
+    pub fn invalidate_name(&mut self, entity_id: &str) {
+        self.entity_names.remove(entity_id);
+    }
+
+    pub fn invalidate_all_names(&mut self) {
+        self.entity_names.clear();
+    }
+
+    fn resolve_name(&mut self, ctx: &Context, entity_id: &str) -> Result<String> {
+        if let Some(name) = self.entity_names.get(entity_id) {
+            return Ok(name.clone());
+        }
+
+        let name = ctx.database().get_entity(entity_id)?.name;
+        self.entity_names.insert(entity_id.to_string(), name.clone());
+        Ok(name)
+    }
+}
+
+impl WorkerTrait for Worker {
+    fn intialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "initialize");
+
+        ctx.logger().info(
+            format!("[{}] Initializing enrich worker", c).as_str(),
+        );
+        self.ctx = Some(ctx);
+        Ok(())
+    }
+
+    fn do_work(&mut self, _ctx: Context) -> Result<()> {
+        Ok(())
+    }
+
+    fn deinitialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "deinitialize");
+
+        ctx.logger().info(
+            format!("[{}] Deinitializing enrich worker", c).as_str(),
+        );
+        self.ctx = None;
+        Ok(())
+    }
+
+    fn process_events(&mut self) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "process_events");
+
+        let Some(ctx) = self.ctx.clone() else {
+            return Ok(());
+        };
+
+        let mut notifications = vec![];
+        for receiver in &self.receivers.notifications {
+            while let Ok(notification) = receiver.try_recv() {
+                notifications.push(notification);
+            }
+        }
+
+        for notification in notifications {
+            let entity_id = notification.current.entity_id();
+            let name = match self.resolve_name(&ctx, &entity_id) {
+                Ok(name) => name,
+                Err(e) => {
+                    ctx.logger().warning(&format!(
+                        "[{}] Failed to resolve name of entity '{}': {}",
+                        c, entity_id, e
+                    ));
+                    entity_id
+                }
+            };
+
+            self.emitters
+                .enriched
+                .emit(EnrichedNotification { name, notification });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::framework::application::Context;
+use crate::framework::workers::common::WorkerTrait;
+use crate::schema::field::{Field, RawField};
+use crate::schema::value::RawValue;
+
+use crate::Result;
+
+struct Mirror {
+    source: Field,
+    target: Field,
+    last_written: Option<RawValue>,
+}
+
+pub struct Worker {
+    mirrors: Vec<Mirror>,
+}
+
+impl Worker {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { mirrors: vec![] }
+    }
+
+    pub fn add_mirror(
+        &mut self,
+        source_entity_id: &str,
+        source_field: &str,
+        target_entity_id: &str,
+        target_field: &str,
+    ) {
+        self.mirrors.push(Mirror {
+            source: RawField::new(source_entity_id, source_field).into_field(),
+            target: RawField::new(target_entity_id, target_field).into_field(),
+            last_written: None,
+        });
+    }
+}
+
+impl WorkerTrait for Worker {
+    fn intialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "initialize");
+
+        ctx.logger().info(
+            format!("[{}] Initializing mirror worker with {} mirror(s)", c, self.mirrors.len()).as_str(),
+        );
+        Ok(())
+    }
+
+    fn do_work(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "do_work");
+
+        if self.mirrors.is_empty() || !ctx.database().connected() {
+            return Ok(());
+        }
+
+        let sources = self.mirrors.iter().map(|m| m.source.clone()).collect();
+        ctx.database().read(&sources)?;
+
+        let mut dirty = vec![];
+        for (i, mirror) in self.mirrors.iter().enumerate() {
+            let value = mirror.source.value().into_raw();
+            if value.is_unspecified() || mirror.last_written.as_ref() == Some(&value) {
+                continue;
+            }
+
+            mirror.target.update_value(value.into_value());
+            dirty.push(i);
+        }
+
+        if dirty.is_empty() {
+            return Ok(());
+        }
+
+        let targets = dirty.iter().map(|i| self.mirrors[*i].target.clone()).collect();
+        ctx.database().write(&targets)?;
+
+        for i in dirty {
+            let mirror = &mut self.mirrors[i];
+            mirror.last_written = Some(mirror.target.value().into_raw());
+
+            ctx.logger().debug(&format!(
+                "[{}] Mirrored field '{}' of entity '{}' to field '{}' of entity '{}'",
+                c,
+                mirror.source.name(),
+                mirror.source.entity_id(),
+                mirror.target.name(),
+                mirror.target.entity_id()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn deinitialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "deinitialize");
+
+        ctx.logger().info(
+            format!("[{}] Deinitializing mirror worker", c).as_str(),
+        );
+        Ok(())
+    }
+
+    fn process_events(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use crate::framework::application::Context;
+use crate::framework::util::Throttle;
+use crate::framework::workers::common::WorkerTrait;
+use crate::schema::field::{Field, RawField};
+
+use crate::Result;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+struct Poll {
+    entity_id: String,
+    field: String,
+    throttle: Throttle,
+    sender: Sender<Field>,
+}
+
+/// Reads fields on their own intervals and sends each reading to a channel,
+/// as a time series source that needs no notifications. Polling happens in
+/// `do_work`, so intervals shorter than the application's loop interval are
+/// effectively rounded up to it. Due fields are read in one batch.
+pub struct Worker {
+    polls: Vec<Poll>,
+}
+
+impl Worker {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { polls: vec![] }
+    }
+
+    /// Starts reading `field` of `entity_id` every `interval`, beginning with
+    /// the next tick. Each reading is a separate `Field`. The poll stops
+    /// once the receiver is dropped, or on `stop`.
+    pub fn poll_field(&mut self, entity_id: &str, field: &str, interval: Duration) -> Receiver<Field> {
+        let (sender, receiver) = channel();
+        self.polls.push(Poll {
+            entity_id: entity_id.to_string(),
+            field: field.to_string(),
+            throttle: Throttle::new(interval),
+            sender,
+        });
+        receiver
+    }
+
+    /// Stops every poll of `field` of `entity_id`, disconnecting their
+    /// receivers.
+    pub fn stop(&mut self, entity_id: &str, field: &str) {
+        self.polls
+            .retain(|p| p.entity_id != entity_id || p.field != field);
+    }
+}
+
+impl WorkerTrait for Worker {
+    fn intialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "initialize");
+
+        ctx.logger().info(
+            format!("[{}] Initializing poll worker with {} poll(s)", c, self.polls.len()).as_str(),
+        );
+        Ok(())
+    }
+
+    fn do_work(&mut self, ctx: Context) -> Result<()> {
+        if self.polls.is_empty() || !ctx.database().connected() {
+            return Ok(());
+        }
+
+        let mut due = vec![];
+        for (i, poll) in self.polls.iter_mut().enumerate() {
+            if poll.throttle.should_run() {
+                due.push((i, RawField::new(poll.entity_id.as_str(), poll.field.as_str()).into_field()));
+            }
+        }
+
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        let requests = due.iter().map(|(_, field)| field.clone()).collect();
+        ctx.database().read(&requests)?;
+
+        let mut disconnected = vec![];
+        for (i, field) in due {
+            if self.polls[i].sender.send(field).is_err() {
+                disconnected.push(i);
+            }
+        }
+
+        for i in disconnected.into_iter().rev() {
+            self.polls.remove(i);
+        }
+
+        Ok(())
+    }
+
+    fn deinitialize(&mut self, ctx: Context) -> Result<()> {
+        let c = format!("{}::{}", std::any::type_name::<Self>(), "deinitialize");
+
+        ctx.logger().info(
+            format!("[{}] Deinitializing poll worker", c).as_str(),
+        );
+        Ok(())
+    }
+
+    fn process_events(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use crate::framework::client::Client;
+    use crate::framework::database::Database;
+    use crate::framework::logger::Logger;
+    use crate::loggers::common::{LogLevel, LoggerTrait};
+    use crate::schema::value::RawValue;
+
+    struct Quiet;
+
+    impl LoggerTrait for Quiet {
+        fn log(&self, _level: &LogLevel, _message: &str) {}
+
+        fn level(&self) -> LogLevel {
+            LogLevel::Error
+        }
+    }
+
+    fn connected_context() -> (mock::Client, Context) {
+        let mock = mock::Client::new();
+        let db = Database::new(Client::new(mock.clone()));
+        db.connect().unwrap();
+        (mock, Context::new(db, Logger::new(Quiet)))
+    }
+
+    /// Fields read since the last call, in order.
+    fn take_reads(mock: &mock::Client) -> Vec<String> {
+        mock.take_operations()
+            .into_iter()
+            .filter_map(|op| match op {
+                mock::Operation::Read { field, .. } => Some(field),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fields_are_read_once_per_interval() {
+        let (mock, ctx) = connected_context();
+        mock.set_value("Sensor1", "Reading", RawValue::Integer(7));
+        let mut worker = Worker::new();
+        let fast = worker.poll_field("Sensor1", "Reading", Duration::from_millis(20));
+        let slow = worker.poll_field("Sensor1", "Status", Duration::from_secs(60));
+
+        worker.do_work(ctx.clone()).unwrap();
+        worker.do_work(ctx.clone()).unwrap();
+        assert_eq!(take_reads(&mock), vec!["Reading", "Status"]);
+
+        std::thread::sleep(Duration::from_millis(30));
+        worker.do_work(ctx.clone()).unwrap();
+        assert_eq!(take_reads(&mock), vec!["Reading"]);
+
+        assert_eq!(fast.try_iter().count(), 2);
+        assert_eq!(slow.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn dropping_the_receiver_stops_its_poll() {
+        let (mock, ctx) = connected_context();
+        let mut worker = Worker::new();
+        let kept = worker.poll_field("Sensor1", "Reading", Duration::ZERO);
+        drop(worker.poll_field("Sensor1", "Status", Duration::ZERO));
+
+        worker.do_work(ctx.clone()).unwrap();
+        assert_eq!(take_reads(&mock), vec!["Reading", "Status"]);
+
+        worker.do_work(ctx.clone()).unwrap();
+        assert_eq!(take_reads(&mock), vec!["Reading"]);
+        assert_eq!(kept.try_iter().count(), 2);
+    }
+}
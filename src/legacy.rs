@@ -0,0 +1,20 @@
+//! The type names of the older flat `qdb::` API, for code being ported to the
+//! current modules. There are no separate legacy types: each old name
+//! refers to the same type as its new counterpart, so these aliases are all
+//! the interop needed and no `From`/`Into` conversions exist. Import them
+//! from here, e.g. `use qdb::legacy::DatabaseEntity;`.
+
+pub use crate::clients::rest::Client as RestClient;
+pub use crate::framework::application::{Application, Context};
+pub use crate::framework::database::Database;
+pub use crate::framework::events::emitter::Emitter as EventEmitter;
+pub use crate::framework::workers::database::Worker as DatabaseWorker;
+pub use crate::loggers::common::LogLevel;
+pub use crate::loggers::console::Console as ConsoleLogger;
+pub use crate::schema::entity::Entity as DatabaseEntity;
+pub use crate::schema::field::Field as DatabaseField;
+pub use crate::schema::notification::{
+    Config as NotificationConfig, Notification as DatabaseNotification,
+    Token as NotificationToken,
+};
+pub use crate::schema::value::{DatabaseValue, RawValue};
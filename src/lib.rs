@@ -3,5 +3,8 @@ pub type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
 pub mod clients;
 pub mod error;
 pub mod framework;
+#[cfg(feature = "legacy")]
+pub mod legacy;
 pub mod loggers;
-pub mod schema;
\ No newline at end of file
+pub mod prelude;
+pub mod schema;
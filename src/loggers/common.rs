@@ -1,5 +1,5 @@
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -11,6 +11,14 @@ pub enum LogLevel {
 pub trait LoggerTrait {
     fn log(&self, level: &LogLevel, message: &str);
 
+    fn level(&self) -> LogLevel {
+        LogLevel::Trace
+    }
+
+    fn enabled(&self, level: &LogLevel) -> bool {
+        *level >= self.level()
+    }
+
     fn trace(&self, message: &str) {
         self.log(&LogLevel::Trace, message);
     }
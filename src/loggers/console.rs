@@ -7,13 +7,17 @@ pub struct Console {
 
 impl Console {
     pub fn new(level: LogLevel) -> Self {
-        Console { level: level }
+        Console { level }
     }
 }
 
 impl LoggerTrait for Console {
+    fn level(&self) -> LogLevel {
+        self.level
+    }
+
     fn log(&self, level: &LogLevel, message: &str) {
-        if *level >= self.level {
+        if self.enabled(level) {
             println!(
                 "{} | {} | {}",
                 Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
@@ -0,0 +1,34 @@
+pub use crate::clients::common::ClientTrait;
+pub use crate::clients::reconnect::ReconnectGate;
+pub use crate::clients::rest::{Client as RestClient, Pipe};
+pub use crate::error::{Error, ResultExt};
+pub use crate::framework::application::{
+    Application, ApplicationTrait, BoolFlag, Context, RestartPolicy,
+};
+pub use crate::framework::batch::BatchCtx;
+pub use crate::framework::client::Client;
+pub use crate::framework::config::ConfigReader;
+pub use crate::framework::database::{Aggregation, Database, ReadOnlyFieldPolicy};
+pub use crate::framework::events::bounded::{BoundedReceiver, FullChannelPolicy};
+pub use crate::framework::events::emitter::{Emitter, LatchingEmitter};
+pub use crate::framework::events::stream::{NotificationIterExt, NotificationStream};
+pub use crate::framework::logger::{Logger, SyncLogger};
+pub use crate::framework::notification::{
+    NotificationManager, NotificationSink, NotificationStats, SinkHandle,
+};
+pub use crate::framework::util::Throttle;
+pub use crate::framework::workers::common::WorkerTrait;
+pub use crate::loggers::common::{LogLevel, LoggerTrait};
+pub use crate::loggers::console::Console;
+pub use crate::schema::entity::{
+    DatabaseEntity, Entity, EntityMetadata, EntityPage, EntitySchema, FieldSchema,
+};
+pub use crate::schema::field::{Field, RawField};
+pub use crate::schema::notification::{
+    Config as NotificationConfig, Lease as NotificationLease, Notification,
+    Token as NotificationToken,
+};
+pub use crate::schema::server::ServerInfo;
+pub use crate::schema::snapshot::{FieldChange, Snapshot, SnapshotField};
+pub use crate::schema::value::{DatabaseValue, DisplayOpts, RawValue, ValueKind};
+pub use crate::Result;
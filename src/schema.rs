@@ -2,4 +2,6 @@
 pub mod entity;
 pub mod field;
 pub mod notification;
+pub mod server;
+pub mod snapshot;
 pub mod value;
\ No newline at end of file
@@ -1,3 +1,5 @@
+use chrono::{DateTime, Utc};
+
 use crate::schema::field::{Field, RawField};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -7,6 +9,53 @@ pub struct Entity {
     pub name: String,
 }
 
+pub type DatabaseEntity = Entity;
+
+/// One page of a paged entity listing. `next_cursor` is passed back to
+/// fetch the following page and is `None` on the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityPage {
+    pub entities: Vec<Entity>,
+    pub next_cursor: Option<String>,
+}
+
+/// Lifecycle details the server keeps about an entity. Each is `None` when
+/// the server does not report it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityMetadata {
+    pub created_at: Option<DateTime<Utc>>,
+    pub modified_at: Option<DateTime<Utc>>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSchema {
+    pub name: String,
+    pub type_name: String,
+    /// Set for fields the server derives itself, which can't be written.
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntitySchema {
+    pub name: String,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl EntitySchema {
+    pub fn has_field(&self, name: &str) -> bool {
+        self.fields.iter().any(|f| f.name == name)
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    pub fn field_names(&self) -> Vec<String> {
+        self.fields.iter().map(|f| f.name.clone()).collect()
+    }
+}
+
 impl Entity {
     pub fn new(id: &str, type_name: &str, name: &str) -> Self {
         Entity {
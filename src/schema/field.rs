@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use crate::schema::value::{DatabaseValue, RawValue};
 
 pub type FieldRef = Rc<RefCell<RawField>>;
+pub type ChangeCallback = Box<dyn FnMut(&RawValue)>;
 
 pub struct RawField {
     pub entity_id: String,
@@ -11,6 +12,8 @@ pub struct RawField {
     pub value: DatabaseValue,
     pub write_time: DateTime<Utc>,
     pub writer_id: String,
+    pub unit: Option<String>,
+    observers: Vec<ChangeCallback>,
 }
 
 impl RawField {
@@ -34,12 +37,27 @@ impl RawField {
         self.writer_id.clone()
     }
 
+    pub fn unit(&self) -> Option<String> {
+        self.unit.clone()
+    }
+
     pub fn update_entity_id(&mut self, entity_id: &str) {
         self.entity_id = entity_id.into();
     }
 
     pub fn update_value(&mut self, value: DatabaseValue) {
         self.value = value;
+
+        if !self.observers.is_empty() {
+            let raw = self.value.clone().into_raw();
+            for observer in &mut self.observers {
+                observer(&raw);
+            }
+        }
+    }
+
+    pub fn on_change(&mut self, callback: ChangeCallback) {
+        self.observers.push(callback);
     }
 
     pub fn update_write_time(&mut self, write_time: DateTime<Utc>) {
@@ -50,6 +68,10 @@ impl RawField {
         self.writer_id = writer_id.into();
     }
 
+    pub fn update_unit(&mut self, unit: Option<String>) {
+        self.unit = unit;
+    }
+
     pub fn update_name(&mut self, name: &str) {
         self.name = name.into();
     }
@@ -61,6 +83,8 @@ impl RawField {
             value: DatabaseValue::new(RawValue::Unspecified),
             write_time: Utc::now(),
             writer_id: "".to_string(),
+            unit: None,
+            observers: Vec::new(),
         }
     }
 
@@ -75,6 +99,8 @@ impl RawField {
             value: DatabaseValue::new(value),
             write_time: Utc::now(),
             writer_id: "".to_string(),
+            unit: None,
+            observers: Vec::new(),
         }
     }
 
@@ -104,6 +130,8 @@ impl Field {
             value: field.value(),
             write_time: field.write_time(),
             writer_id: field.writer_id(),
+            unit: field.unit(),
+            observers: Vec::new(),
         }
     }
 
@@ -127,6 +155,11 @@ impl Field {
         self.0.borrow().writer_id()
     }
 
+    /// Unit of the value, such as "°C", when the server reports one.
+    pub fn unit(&self) -> Option<String> {
+        self.0.borrow().unit()
+    }
+
     pub fn update_entity_id(&self, entity_id: &str) {
         self.0.borrow_mut().update_entity_id(entity_id);
     }
@@ -135,14 +168,30 @@ impl Field {
         self.0.borrow_mut().update_value(value);
     }
 
+    /// Registers a callback invoked with the new value whenever `update_value`
+    /// replaces it locally. The field is borrowed while callbacks run, so a
+    /// callback must not access the field it is attached to.
+    pub fn on_change(&self, callback: ChangeCallback) {
+        self.0.borrow_mut().on_change(callback);
+    }
+
     pub fn update_write_time(&self, write_time: DateTime<Utc>) {
         self.0.borrow_mut().update_write_time(write_time);
     }
 
+    /// Sets the write time to now, leaving the value untouched.
+    pub fn touch(&self) {
+        self.update_write_time(Utc::now());
+    }
+
     pub fn update_writer_id(&self, writer_id: &str) {
         self.0.borrow_mut().update_writer_id(writer_id);
     }
 
+    pub fn update_unit(&self, unit: Option<String>) {
+        self.0.borrow_mut().update_unit(unit);
+    }
+
     pub fn update_name(&self, name: &str) {
         self.0.borrow_mut().update_name(name);
     }
@@ -195,9 +244,21 @@ impl Field {
         self
     }
 
+    pub fn set_blob_value(&self, value: Vec<u8>) -> &Self {
+        self.0
+            .borrow_mut()
+            .update_value(DatabaseValue::new(RawValue::Blob(value)));
+        self
+    }
+
     pub fn set_unspecified_value(&self) -> &Self {
         self.0.borrow_mut().update_value(DatabaseValue::new(RawValue::Unspecified));
         self
     }
 
+    pub fn with_writer(&self, writer_id: &str) -> &Self {
+        self.0.borrow_mut().update_writer_id(writer_id);
+        self
+    }
+
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
-use crate::schema::field::Field;
+use chrono::{DateTime, Utc};
+
+use crate::framework::database::Database;
+use crate::Result;
+use crate::schema::field::{Field, RawField};
 
 #[derive(Clone)]
 pub struct Notification {
@@ -8,6 +12,26 @@ pub struct Notification {
     pub context: Vec<Field>,
 }
 
+impl Notification {
+    pub fn new(
+        token: impl Into<String>,
+        current: RawField,
+        previous: RawField,
+        context: Vec<RawField>,
+    ) -> Self {
+        Notification {
+            token: token.into(),
+            current: current.into_field(),
+            previous: previous.into_field(),
+            context: context.into_iter().map(RawField::into_field).collect(),
+        }
+    }
+
+    pub fn context_field(&self, name: &str) -> Option<&Field> {
+        self.context.iter().find(|f| f.name() == name)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Config {
     pub entity_id: String,
@@ -17,12 +41,63 @@ pub struct Config {
     pub context: Vec<String>,
 }
 
+impl Config {
+    /// Returns a copy whose context also holds `fields`, keeping the
+    /// existing order and skipping fields already present.
+    pub fn with_context(&self, fields: &[&str]) -> Config {
+        let mut config = self.clone();
+        for field in fields {
+            if !config.context.iter().any(|c| c == field) {
+                config.context.push(field.to_string());
+            }
+        }
+
+        config
+    }
+
+    /// Returns a copy whose context holds every field of the entity type's
+    /// schema, besides the notifying field itself. Every notification then
+    /// carries the entity's full state, which multiplies the size of each
+    /// delivery; prefer `with_inferred_context_filtered` outside of
+    /// exploratory or monitoring use.
+    pub fn with_inferred_context(&self, db: &Database) -> Result<Config> {
+        self.with_inferred_context_filtered(db, |_| true)
+    }
+
+    /// Like `with_inferred_context`, but only adds schema fields for which
+    /// `filter` returns true. Fields already in the context are kept.
+    pub fn with_inferred_context_filtered(
+        &self,
+        db: &Database,
+        filter: fn(&str) -> bool,
+    ) -> Result<Config> {
+        let schema = db.get_entity_schema(&self.entity_type)?;
+
+        let mut config = self.clone();
+        for name in schema.field_names() {
+            if name != config.field && filter(&name) && !config.context.contains(&name) {
+                config.context.push(name);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Token(String);
 
-impl Into<String> for &Token {
-    fn into(self) -> String {
-        self.0.clone()
+/// A registration's token along with when the server will drop it, if the
+/// server puts a time limit on registrations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    pub token: Token,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<&Token> for String {
+    fn from(token: &Token) -> Self {
+        token.0.clone()
     }
 }
 
@@ -37,3 +112,23 @@ impl From<&str> for Token {
         Token(s.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_context_merges_missing_fields() {
+        let config = Config {
+            entity_id: "Door1".to_string(),
+            entity_type: "Door".to_string(),
+            field: "State".to_string(),
+            notify_on_change: true,
+            context: vec!["Alarm".to_string()],
+        };
+
+        let merged = config.with_context(&["Alarm", "Zone"]);
+        assert_eq!(merged.context, vec!["Alarm".to_string(), "Zone".to_string()]);
+        assert_eq!(config.context, vec!["Alarm".to_string()]);
+    }
+}
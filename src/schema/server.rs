@@ -0,0 +1,9 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub version: String,
+    pub uptime: Option<Duration>,
+    pub entity_counts: HashMap<String, u64>,
+}
@@ -0,0 +1,84 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::field::Field;
+use crate::schema::value::RawValue;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotField {
+    pub value: RawValue,
+    pub write_time: DateTime<Utc>,
+    pub writer_id: String,
+}
+
+impl From<&Field> for SnapshotField {
+    fn from(field: &Field) -> Self {
+        SnapshotField {
+            value: field.value().into_raw(),
+            write_time: field.write_time(),
+            writer_id: field.writer_id(),
+        }
+    }
+}
+
+/// A field whose value differs between two snapshots. `old` is `None` for
+/// fields only in the newer snapshot and `new` is `None` for fields only in
+/// the older one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub entity_id: String,
+    pub field: String,
+    pub old: Option<RawValue>,
+    pub new: Option<RawValue>,
+}
+
+/// Point-in-time copy of field values keyed by entity id, then field name.
+/// Keys are kept sorted so serialized snapshots compare cleanly.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub entities: BTreeMap<String, BTreeMap<String, SnapshotField>>,
+}
+
+impl Snapshot {
+    pub fn new() -> Self {
+        Snapshot::default()
+    }
+
+    pub fn insert(&mut self, field: &Field) {
+        self.entities
+            .entry(field.entity_id())
+            .or_default()
+            .insert(field.name(), SnapshotField::from(field));
+    }
+
+    pub fn get(&self, entity_id: &str, field: &str) -> Option<&SnapshotField> {
+        self.entities.get(entity_id).and_then(|fields| fields.get(field))
+    }
+
+    /// Lists the fields whose values differ from `self` to `other`, ordered
+    /// by entity id and field name. Write times and writers are ignored.
+    pub fn diff(&self, other: &Snapshot) -> Vec<FieldChange> {
+        let mut keys: BTreeSet<(&String, &String)> = BTreeSet::new();
+        for snapshot in [self, other] {
+            for (entity_id, fields) in &snapshot.entities {
+                keys.extend(fields.keys().map(|field| (entity_id, field)));
+            }
+        }
+
+        keys.into_iter()
+            .filter_map(|(entity_id, field)| {
+                let old = self.get(entity_id, field).map(|f| f.value.clone());
+                let new = other.get(entity_id, field).map(|f| f.value.clone());
+
+                (old != new).then(|| FieldChange {
+                    entity_id: entity_id.clone(),
+                    field: field.clone(),
+                    old,
+                    new,
+                })
+            })
+            .collect()
+    }
+}
@@ -1,10 +1,67 @@
 use std::cell::RefCell;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
 use crate::Result;
 use crate::error::Error;
 
-#[derive(Debug, Clone, PartialEq)]
+const TYPE_URL_PREFIX: &str = "type.googleapis.com/qdb.";
+
+/// Earliest and latest seconds representable by the server's timestamps,
+/// 0001-01-01T00:00:00Z and 9999-12-31T23:59:59Z.
+const MIN_TIMESTAMP_SECONDS: i64 = -62_135_596_800;
+const MAX_TIMESTAMP_SECONDS: i64 = 253_402_300_799;
+
+/// Encodes `t` as seconds and nanos since the epoch. Seconds are floored, so
+/// nanos always count forward from them: 1969-12-31T23:59:59.25Z becomes
+/// seconds -1 and nanos 250000000, never seconds 0 and negative nanos.
+fn encode_timestamp(t: &DateTime<Utc>) -> Result<Value> {
+    let seconds = t.timestamp();
+    if !(MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&seconds) {
+        return Err(Error::from_database_field(&format!(
+            "Timestamp {} is outside the range the server can store",
+            t.to_rfc3339()
+        )));
+    }
+
+    // chrono represents a leap second as nanos past 1e9; fold it into the
+    // last representable instant of the preceding second.
+    let nanos = t.timestamp_subsec_nanos().min(999_999_999);
+
+    let mut raw = Map::new();
+    raw.insert("seconds".to_string(), Value::Number(Number::from(seconds)));
+    raw.insert("nanos".to_string(), Value::Number(Number::from(nanos as i64)));
+    Ok(Value::Object(raw))
+}
+
+/// Decodes a timestamp sent either as an RFC 3339 string or in the
+/// seconds and nanos form produced by `encode_timestamp`.
+fn decode_timestamp(raw: &Value) -> Result<DateTime<Utc>> {
+    if let Some(text) = raw.as_str() {
+        return Ok(DateTime::parse_from_rfc3339(text)?.to_utc());
+    }
+
+    // int64 fields may arrive as JSON strings, as protobuf's JSON mapping
+    // produces them.
+    let seconds = raw
+        .get("seconds")
+        .and_then(|v| v.as_i64().or_else(|| v.as_str()?.parse().ok()))
+        .unwrap_or(0);
+    let nanos = raw.get("nanos").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    u32::try_from(nanos)
+        .ok()
+        .filter(|nanos| *nanos < 1_000_000_000)
+        .and_then(|nanos| DateTime::from_timestamp(seconds, nanos))
+        .ok_or(Error::from_database_field("Timestamp is not valid"))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RawValue {
     Unspecified,
     String(String),
@@ -15,6 +72,21 @@ pub enum RawValue {
     Timestamp(DateTime<Utc>),
     ConnectionState(String),
     GarageDoorState(String),
+    Blob(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    Unspecified,
+    String,
+    Integer,
+    Float,
+    Boolean,
+    EntityReference,
+    Timestamp,
+    ConnectionState,
+    GarageDoorState,
+    Blob,
 }
 
 impl RawValue {
@@ -22,6 +94,190 @@ impl RawValue {
         DatabaseValue::new(self)
     }
 
+    /// Like `==`, except that integers and floats compare numerically across
+    /// the two types, so `Integer(1)` loosely equals `Float(1.0)`. The
+    /// comparison is exact: a float only equals an integer it represents
+    /// without rounding, so large integers don't match their neighbours.
+    pub fn loosely_eq(&self, other: &RawValue) -> bool {
+        match (self, other) {
+            (RawValue::Integer(i), RawValue::Float(f)) | (RawValue::Float(f), RawValue::Integer(i)) => {
+                f.fract() == 0.0 && *f >= i64::MIN as f64 && *f < i64::MAX as f64 && *f as i64 == *i
+            }
+            _ => self == other,
+        }
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            RawValue::Unspecified => ValueKind::Unspecified,
+            RawValue::String(_) => ValueKind::String,
+            RawValue::Integer(_) => ValueKind::Integer,
+            RawValue::Float(_) => ValueKind::Float,
+            RawValue::Boolean(_) => ValueKind::Boolean,
+            RawValue::EntityReference(_) => ValueKind::EntityReference,
+            RawValue::Timestamp(_) => ValueKind::Timestamp,
+            RawValue::ConnectionState(_) => ValueKind::ConnectionState,
+            RawValue::GarageDoorState(_) => ValueKind::GarageDoorState,
+            RawValue::Blob(_) => ValueKind::Blob,
+        }
+    }
+
+    /// Parses `input` into the variant named by `kind`. Booleans must be
+    /// "true" or "false", timestamps RFC3339, blobs base64, and `Unspecified`
+    /// only accepts an empty string.
+    pub fn parse(kind: ValueKind, input: &str) -> Result<RawValue> {
+        let invalid = |expected: &str| -> Box<dyn std::error::Error> {
+            Error::from_database_field(&format!("Cannot parse '{}' as {}", input, expected))
+        };
+
+        match kind {
+            ValueKind::Unspecified if input.is_empty() => Ok(RawValue::Unspecified),
+            ValueKind::Unspecified => Err(invalid("an unspecified value")),
+            ValueKind::String => Ok(RawValue::String(input.into())),
+            ValueKind::Integer => input
+                .trim()
+                .parse::<i64>()
+                .map(RawValue::Integer)
+                .map_err(|_| invalid("an integer")),
+            ValueKind::Float => input
+                .trim()
+                .parse::<f64>()
+                .map(RawValue::Float)
+                .map_err(|_| invalid("a float")),
+            ValueKind::Boolean => match input.trim() {
+                "true" => Ok(RawValue::Boolean(true)),
+                "false" => Ok(RawValue::Boolean(false)),
+                _ => Err(invalid("a boolean")),
+            },
+            ValueKind::EntityReference => Ok(RawValue::EntityReference(input.trim().into())),
+            ValueKind::Timestamp => DateTime::parse_from_rfc3339(input.trim())
+                .map(|t| RawValue::Timestamp(t.with_timezone(&Utc)))
+                .map_err(|_| invalid("an RFC3339 timestamp")),
+            ValueKind::ConnectionState => Ok(RawValue::ConnectionState(input.trim().into())),
+            ValueKind::GarageDoorState => Ok(RawValue::GarageDoorState(input.trim().into())),
+            ValueKind::Blob => BASE64
+                .decode(input.trim())
+                .map(RawValue::Blob)
+                .map_err(|_| invalid("base64")),
+        }
+    }
+
+    /// Encodes the value the way the server's JSON API carries it: an
+    /// object with the protobuf type URL under `@type` and the value under
+    /// `raw`. Timestamps are written as seconds and nanos, blobs as base64.
+    /// Fails for unspecified values, non-finite floats and out of range
+    /// timestamps.
+    pub fn to_protobuf_json(&self) -> Result<Value> {
+        let (type_name, raw) = match self {
+            RawValue::Unspecified => {
+                return Err(Error::from_unsupported(
+                    "Unspecified values have no protobuf JSON form",
+                ))
+            }
+            RawValue::String(s) => ("String", Value::String(s.clone())),
+            RawValue::Integer(i) => ("Int", Value::Number(Number::from(*i))),
+            RawValue::Float(f) => (
+                "Float",
+                Value::Number(Number::from_f64(*f).ok_or(Error::from_database_field(
+                    "non-finite float cannot be written",
+                ))?),
+            ),
+            RawValue::Boolean(b) => ("Bool", Value::Bool(*b)),
+            RawValue::EntityReference(e) => ("EntityReference", Value::String(e.clone())),
+            RawValue::Timestamp(t) => ("Timestamp", encode_timestamp(t)?),
+            RawValue::ConnectionState(c) => ("ConnectionState", Value::String(c.clone())),
+            RawValue::GarageDoorState(g) => ("GarageDoorState", Value::String(g.clone())),
+            RawValue::Blob(b) => ("Blob", Value::String(BASE64.encode(b))),
+        };
+
+        let mut value = Map::new();
+        value.insert(
+            "@type".to_string(),
+            Value::String(format!("{}{}", TYPE_URL_PREFIX, type_name)),
+        );
+        value.insert("raw".to_string(), raw);
+        Ok(Value::Object(value))
+    }
+
+    /// Decodes a value in the form produced by `to_protobuf_json`. Integers
+    /// may also be strings and timestamps RFC 3339 strings, as the server
+    /// sends them. Unknown type URLs are reported as unsupported.
+    pub fn from_protobuf_json(value: &Value) -> Result<RawValue> {
+        let type_url = value
+            .get("@type")
+            .and_then(|v| v.as_str())
+            .ok_or(Error::from_database_field("Value type is not valid"))?;
+
+        let type_name = type_url.strip_prefix(TYPE_URL_PREFIX).unwrap_or_default();
+        let raw = value.get("raw");
+        let invalid = || -> Box<dyn std::error::Error> {
+            Error::from_database_field(&format!("Value of type '{}' is not valid", type_url))
+        };
+        let raw_str = || raw.and_then(|v| v.as_str()).map(|v| v.to_string()).ok_or_else(invalid);
+
+        Ok(match type_name {
+            "String" => RawValue::String(raw_str()?),
+            "Int" => RawValue::Integer(
+                raw.and_then(|v| v.as_i64().or_else(|| v.as_str()?.parse().ok()))
+                    .ok_or_else(invalid)?,
+            ),
+            "Float" => RawValue::Float(raw.and_then(|v| v.as_f64()).ok_or_else(invalid)?),
+            "Bool" => RawValue::Boolean(raw.and_then(|v| v.as_bool()).ok_or_else(invalid)?),
+            "EntityReference" => RawValue::EntityReference(raw_str()?),
+            "Timestamp" => RawValue::Timestamp(decode_timestamp(raw.ok_or_else(invalid)?)?),
+            "ConnectionState" => RawValue::ConnectionState(raw_str()?),
+            "GarageDoorState" => RawValue::GarageDoorState(raw_str()?),
+            // Protobuf JSON leaves out empty bytes, so a missing `raw` is an
+            // empty blob.
+            "Blob" => match raw {
+                None => RawValue::Blob(vec![]),
+                Some(_) => RawValue::Blob(BASE64.decode(raw_str()?).map_err(|_| invalid())?),
+            },
+            _ => {
+                return Err(Error::from_unsupported(&format!(
+                    "Unknown value type '{}'",
+                    type_url
+                )))
+            }
+        })
+    }
+
+    /// Converts the value to `kind` where no information is lost: integers
+    /// to floats, whole floats to integers, and between the string-like
+    /// kinds. Unspecified values and values already of `kind` are returned
+    /// unchanged.
+    pub fn coerce_to(&self, kind: ValueKind) -> Result<RawValue> {
+        if self.kind() == kind || self.is_unspecified() {
+            return Ok(self.clone());
+        }
+
+        let text = match self {
+            RawValue::String(s)
+            | RawValue::EntityReference(s)
+            | RawValue::ConnectionState(s)
+            | RawValue::GarageDoorState(s) => Some(s.clone()),
+            _ => None,
+        };
+
+        match (self, kind, text) {
+            (RawValue::Integer(i), ValueKind::Float, _) => Ok(RawValue::Float(*i as f64)),
+            (RawValue::Float(f), ValueKind::Integer, _)
+                if f.fract() == 0.0 && *f >= i64::MIN as f64 && *f < i64::MAX as f64 =>
+            {
+                Ok(RawValue::Integer(*f as i64))
+            }
+            (_, ValueKind::String, Some(s)) => Ok(RawValue::String(s)),
+            (_, ValueKind::EntityReference, Some(s)) => Ok(RawValue::EntityReference(s)),
+            (_, ValueKind::ConnectionState, Some(s)) => Ok(RawValue::ConnectionState(s)),
+            (_, ValueKind::GarageDoorState, Some(s)) => Ok(RawValue::GarageDoorState(s)),
+            _ => Err(Error::from_database_field(&format!(
+                "Cannot coerce {:?} value to {:?}",
+                self.kind(),
+                kind
+            ))),
+        }
+    }
+
     pub fn as_str(&self) -> Result<String> {
         match self {
             RawValue::String(s) => Ok(s.clone()),
@@ -59,6 +315,29 @@ impl RawValue {
         }
     }
 
+    pub fn str_ref(&self) -> Result<&str> {
+        match self {
+            RawValue::String(s) => Ok(s),
+            _ => Err(Error::from_database_field("Value is not a string")),
+        }
+    }
+
+    pub fn entity_reference_ref(&self) -> Result<&str> {
+        match self {
+            RawValue::EntityReference(e) => Ok(e),
+            _ => Err(Error::from_database_field(
+                "Value is not an entity reference",
+            )),
+        }
+    }
+
+    pub fn blob_ref(&self) -> Result<&[u8]> {
+        match self {
+            RawValue::Blob(b) => Ok(b),
+            _ => Err(Error::from_database_field("Value is not a blob")),
+        }
+    }
+
     pub fn as_timestamp(&self) -> Result<DateTime<Utc>> {
         match self {
             RawValue::Timestamp(t) => Ok(*t),
@@ -84,6 +363,76 @@ impl RawValue {
         }
     }
 
+    pub fn as_blob(&self) -> Result<Vec<u8>> {
+        match self {
+            RawValue::Blob(b) => Ok(b.clone()),
+            _ => Err(Error::from_database_field("Value is not a blob")),
+        }
+    }
+
+    pub fn as_opt_str(&self) -> Result<Option<String>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_str().map(Some),
+        }
+    }
+
+    pub fn as_opt_i64(&self) -> Result<Option<i64>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_i64().map(Some),
+        }
+    }
+
+    pub fn as_opt_f64(&self) -> Result<Option<f64>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_f64().map(Some),
+        }
+    }
+
+    pub fn as_opt_bool(&self) -> Result<Option<bool>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_bool().map(Some),
+        }
+    }
+
+    pub fn as_opt_entity_reference(&self) -> Result<Option<String>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_entity_reference().map(Some),
+        }
+    }
+
+    pub fn as_opt_timestamp(&self) -> Result<Option<DateTime<Utc>>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_timestamp().map(Some),
+        }
+    }
+
+    pub fn as_opt_connection_state(&self) -> Result<Option<String>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_connection_state().map(Some),
+        }
+    }
+
+    pub fn as_opt_garage_door_state(&self) -> Result<Option<String>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_garage_door_state().map(Some),
+        }
+    }
+
+    pub fn as_opt_blob(&self) -> Result<Option<Vec<u8>>> {
+        match self {
+            RawValue::Unspecified => Ok(None),
+            _ => self.as_blob().map(Some),
+        }
+    }
+
     pub fn update_str(&mut self, value: String) -> Result<()> {
         match self {
             RawValue::String(s) => {
@@ -170,6 +519,16 @@ impl RawValue {
         }
     }
 
+    pub fn update_blob(&mut self, value: Vec<u8>) -> Result<()> {
+        match self {
+            RawValue::Blob(b) => {
+                *b = value;
+                Ok(())
+            }
+            _ => Err(Error::from_database_field("Value is not a blob")),
+        }
+    }
+
     pub fn set_str(&mut self, value: String) {
         *self = RawValue::String(value);
     }
@@ -202,6 +561,10 @@ impl RawValue {
         *self = RawValue::GarageDoorState(value);
     }
 
+    pub fn set_blob(&mut self, value: Vec<u8>) {
+        *self = RawValue::Blob(value);
+    }
+
     pub fn set_unspecified(&mut self) {
         *self = RawValue::Unspecified;
     }
@@ -241,6 +604,133 @@ impl RawValue {
     pub fn is_garage_door_state(&self) -> bool {
         matches!(self, RawValue::GarageDoorState(_))
     }
+
+    pub fn is_blob(&self) -> bool {
+        matches!(self, RawValue::Blob(_))
+    }
+
+    /// Renders the value for people with the default `DisplayOpts`.
+    /// Unspecified values render as an empty string and blobs as base64.
+    pub fn to_display_string(&self) -> String {
+        self.to_display_string_with(&DisplayOpts::default())
+    }
+
+    pub fn to_display_string_with(&self, opts: &DisplayOpts) -> String {
+        match self {
+            RawValue::Unspecified => String::new(),
+            RawValue::String(s)
+            | RawValue::EntityReference(s)
+            | RawValue::ConnectionState(s)
+            | RawValue::GarageDoorState(s) => s.clone(),
+            RawValue::Integer(i) => i.to_string(),
+            RawValue::Float(f) => match opts.float_precision {
+                Some(precision) => format!("{:.*}", precision, f),
+                None => f.to_string(),
+            },
+            RawValue::Boolean(true) => opts.bool_labels.0.clone(),
+            RawValue::Boolean(false) => opts.bool_labels.1.clone(),
+            RawValue::Timestamp(t) => {
+                let mut rendered = String::new();
+                match &opts.timestamp_format {
+                    Some(format) if write!(rendered, "{}", t.format(format)).is_ok() => rendered,
+                    _ => t.to_rfc3339(),
+                }
+            }
+            RawValue::Blob(b) => BASE64.encode(b),
+        }
+    }
+}
+
+/// Controls how `RawValue::to_display_string_with` renders values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayOpts {
+    /// Digits after the decimal point for floats; `None` prints the
+    /// shortest representation that round-trips.
+    pub float_precision: Option<usize>,
+    /// Labels for true and false, e.g. `("on".into(), "off".into())`.
+    pub bool_labels: (String, String),
+    /// A chrono `strftime` format for timestamps; `None`, or a format chrono
+    /// rejects, uses RFC3339.
+    pub timestamp_format: Option<String>,
+}
+
+impl Default for DisplayOpts {
+    fn default() -> Self {
+        Self {
+            float_precision: None,
+            bool_labels: ("true".to_string(), "false".to_string()),
+            timestamp_format: None,
+        }
+    }
+}
+
+impl Hash for RawValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            RawValue::Unspecified => {}
+            RawValue::String(s) => s.hash(state),
+            RawValue::Integer(i) => i.hash(state),
+            // -0.0 == 0.0, so both must hash alike.
+            RawValue::Float(f) => (if *f == 0.0 { 0.0f64 } else { *f }).to_bits().hash(state),
+            RawValue::Boolean(b) => b.hash(state),
+            RawValue::EntityReference(e) => e.hash(state),
+            RawValue::Timestamp(t) => t.hash(state),
+            RawValue::ConnectionState(c) => c.hash(state),
+            RawValue::GarageDoorState(g) => g.hash(state),
+            RawValue::Blob(b) => b.hash(state),
+        }
+    }
+}
+
+// Entity references and the state enums share a representation with
+// `String`, so they have no `From` impl and must be constructed explicitly.
+impl From<i64> for RawValue {
+    fn from(value: i64) -> Self {
+        RawValue::Integer(value)
+    }
+}
+
+impl From<f64> for RawValue {
+    fn from(value: f64) -> Self {
+        RawValue::Float(value)
+    }
+}
+
+impl From<bool> for RawValue {
+    fn from(value: bool) -> Self {
+        RawValue::Boolean(value)
+    }
+}
+
+impl From<String> for RawValue {
+    fn from(value: String) -> Self {
+        RawValue::String(value)
+    }
+}
+
+impl From<&str> for RawValue {
+    fn from(value: &str) -> Self {
+        RawValue::String(value.to_string())
+    }
+}
+
+impl From<DateTime<Utc>> for RawValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        RawValue::Timestamp(value)
+    }
+}
+
+impl From<Vec<u8>> for RawValue {
+    fn from(value: Vec<u8>) -> Self {
+        RawValue::Blob(value)
+    }
+}
+
+impl<T: Into<RawValue>> From<T> for DatabaseValue {
+    fn from(value: T) -> Self {
+        DatabaseValue::new(value.into())
+    }
 }
 
 type ValueRef = Rc<RefCell<RawValue>>;
@@ -252,6 +742,7 @@ impl DatabaseValue {
         DatabaseValue(Rc::new(RefCell::new(value)))
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Self {
         DatabaseValue(self.0.clone())
     }
@@ -260,6 +751,28 @@ impl DatabaseValue {
         self.0.borrow().clone()
     }
 
+    pub fn kind(&self) -> ValueKind {
+        self.0.borrow().kind()
+    }
+
+    /// Moves the value out without cloning, leaving `Unspecified` behind in
+    /// this value and every handle sharing it.
+    pub fn take(&self) -> RawValue {
+        std::mem::replace(&mut *self.0.borrow_mut(), RawValue::Unspecified)
+    }
+
+    /// Replaces the value with `new` if `pred` holds for the current one,
+    /// returning whether it did. The value is borrowed only for the call.
+    pub fn replace_if(&self, new: RawValue, pred: impl FnOnce(&RawValue) -> bool) -> bool {
+        let mut value = self.0.borrow_mut();
+        if !pred(&value) {
+            return false;
+        }
+
+        *value = new;
+        true
+    }
+
     pub fn as_str(&self) -> Result<String> {
         self.0.borrow().as_str()
     }
@@ -284,6 +797,22 @@ impl DatabaseValue {
         self.0.borrow().as_timestamp()
     }
 
+    /// Runs `f` on the string without cloning it. The value stays borrowed
+    /// while `f` runs, so `f` must not update it through another handle.
+    pub fn with_str<R>(&self, f: impl FnOnce(&str) -> R) -> Result<R> {
+        Ok(f(self.0.borrow().str_ref()?))
+    }
+
+    /// Entity reference counterpart of `with_str`.
+    pub fn with_entity_reference<R>(&self, f: impl FnOnce(&str) -> R) -> Result<R> {
+        Ok(f(self.0.borrow().entity_reference_ref()?))
+    }
+
+    /// Blob counterpart of `with_str`.
+    pub fn with_blob<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Result<R> {
+        Ok(f(self.0.borrow().blob_ref()?))
+    }
+
     pub fn as_connection_state(&self) -> Result<String> {
         self.0.borrow().as_connection_state()
     }
@@ -292,6 +821,46 @@ impl DatabaseValue {
         self.0.borrow().as_garage_door_state()
     }
 
+    pub fn as_blob(&self) -> Result<Vec<u8>> {
+        self.0.borrow().as_blob()
+    }
+
+    pub fn as_opt_str(&self) -> Result<Option<String>> {
+        self.0.borrow().as_opt_str()
+    }
+
+    pub fn as_opt_i64(&self) -> Result<Option<i64>> {
+        self.0.borrow().as_opt_i64()
+    }
+
+    pub fn as_opt_f64(&self) -> Result<Option<f64>> {
+        self.0.borrow().as_opt_f64()
+    }
+
+    pub fn as_opt_bool(&self) -> Result<Option<bool>> {
+        self.0.borrow().as_opt_bool()
+    }
+
+    pub fn as_opt_entity_reference(&self) -> Result<Option<String>> {
+        self.0.borrow().as_opt_entity_reference()
+    }
+
+    pub fn as_opt_timestamp(&self) -> Result<Option<DateTime<Utc>>> {
+        self.0.borrow().as_opt_timestamp()
+    }
+
+    pub fn as_opt_connection_state(&self) -> Result<Option<String>> {
+        self.0.borrow().as_opt_connection_state()
+    }
+
+    pub fn as_opt_garage_door_state(&self) -> Result<Option<String>> {
+        self.0.borrow().as_opt_garage_door_state()
+    }
+
+    pub fn as_opt_blob(&self) -> Result<Option<Vec<u8>>> {
+        self.0.borrow().as_opt_blob()
+    }
+
     pub fn update_str(&self, value: String) -> Result<()> {
         self.0.borrow_mut().update_str(value)
     }
@@ -324,6 +893,10 @@ impl DatabaseValue {
         self.0.borrow_mut().update_garage_door_state(value)
     }
 
+    pub fn update_blob(&self, value: Vec<u8>) -> Result<()> {
+        self.0.borrow_mut().update_blob(value)
+    }
+
     pub fn set_str(&self, value: String) {
         self.0.borrow_mut().set_str(value)
     }
@@ -356,6 +929,10 @@ impl DatabaseValue {
         self.0.borrow_mut().set_garage_door_state(value)
     }
 
+    pub fn set_blob(&self, value: Vec<u8>) {
+        self.0.borrow_mut().set_blob(value)
+    }
+
     pub fn set_unspecified(&self) {
         self.0.borrow_mut().set_unspecified()
     }
@@ -395,4 +972,184 @@ impl DatabaseValue {
     pub fn is_garage_door_state(&self) -> bool {
         self.0.borrow().is_garage_door_state()
     }
-}
\ No newline at end of file
+
+    pub fn is_blob(&self) -> bool {
+        self.0.borrow().is_blob()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loosely_eq_compares_integers_and_floats_numerically() {
+        assert!(RawValue::Integer(1).loosely_eq(&RawValue::Float(1.0)));
+        assert!(RawValue::Float(-3.0).loosely_eq(&RawValue::Integer(-3)));
+        assert!(!RawValue::Integer(1).loosely_eq(&RawValue::Float(1.5)));
+        assert!(!RawValue::Integer(0).loosely_eq(&RawValue::Float(f64::NAN)));
+        assert!(!RawValue::Integer(1).loosely_eq(&RawValue::Boolean(true)));
+        assert!(!RawValue::Integer(1).loosely_eq(&RawValue::String("1".into())));
+        assert!(RawValue::String("a".into()).loosely_eq(&RawValue::String("a".into())));
+    }
+
+    #[test]
+    fn loosely_eq_is_exact_beyond_float_precision() {
+        let big = 1i64 << 53;
+        assert!(RawValue::Integer(big).loosely_eq(&RawValue::Float(big as f64)));
+        // big + 1 rounds to big as a float
+        assert!(!RawValue::Integer(big + 1).loosely_eq(&RawValue::Float(big as f64)));
+        assert!(!RawValue::Integer(i64::MAX).loosely_eq(&RawValue::Float(i64::MAX as f64)));
+        assert!(RawValue::Integer(i64::MIN).loosely_eq(&RawValue::Float(i64::MIN as f64)));
+    }
+
+    #[test]
+    fn timestamps_before_1970_round_trip() {
+        let t = DateTime::parse_from_rfc3339("1969-12-31T23:59:59.25Z").unwrap().to_utc();
+
+        let raw = encode_timestamp(&t).unwrap();
+        assert_eq!(raw, serde_json::json!({ "seconds": -1, "nanos": 250_000_000 }));
+        assert_eq!(decode_timestamp(&raw).unwrap(), t);
+
+        let raw = serde_json::json!({ "seconds": "-1", "nanos": 250_000_000 });
+        assert_eq!(decode_timestamp(&raw).unwrap(), t);
+
+        let t = DateTime::parse_from_rfc3339("1901-06-01T12:00:00.000000001Z").unwrap().to_utc();
+        assert_eq!(decode_timestamp(&encode_timestamp(&t).unwrap()).unwrap(), t);
+    }
+
+    #[test]
+    fn parse_accepts_each_kind() {
+        let t = DateTime::parse_from_rfc3339("2024-05-01T08:30:00Z").unwrap().to_utc();
+        let cases = [
+            (ValueKind::Unspecified, "", RawValue::Unspecified),
+            (ValueKind::String, " text ", RawValue::String(" text ".into())),
+            (ValueKind::Integer, " -42 ", RawValue::Integer(-42)),
+            (ValueKind::Float, "2.5", RawValue::Float(2.5)),
+            (ValueKind::Boolean, "true", RawValue::Boolean(true)),
+            (ValueKind::Boolean, "false", RawValue::Boolean(false)),
+            (ValueKind::EntityReference, " Door1 ", RawValue::EntityReference("Door1".into())),
+            (ValueKind::Timestamp, "2024-05-01T10:30:00+02:00", RawValue::Timestamp(t)),
+            (ValueKind::ConnectionState, "Connected", RawValue::ConnectionState("Connected".into())),
+            (ValueKind::GarageDoorState, "Opened", RawValue::GarageDoorState("Opened".into())),
+            (ValueKind::Blob, "AAH/", RawValue::Blob(vec![0, 1, 255])),
+        ];
+
+        for (kind, input, expected) in cases {
+            assert_eq!(RawValue::parse(kind, input).unwrap(), expected, "{:?} '{}'", kind, input);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_input() {
+        let cases = [
+            (ValueKind::Unspecified, "x"),
+            (ValueKind::Integer, "4.2"),
+            (ValueKind::Float, "two"),
+            (ValueKind::Boolean, "yes"),
+            (ValueKind::Timestamp, "2024-05-01"),
+            (ValueKind::Blob, "not base64!"),
+        ];
+
+        for (kind, input) in cases {
+            let e = RawValue::parse(kind, input).unwrap_err();
+            assert!(e.to_string().contains(input), "{:?}: {}", kind, e);
+        }
+    }
+
+    #[test]
+    fn display_string_uses_float_precision() {
+        assert_eq!(RawValue::Float(2.5).to_display_string(), "2.5");
+        assert_eq!(RawValue::Float(0.1 + 0.2).to_display_string(), "0.30000000000000004");
+
+        let opts = DisplayOpts {
+            float_precision: Some(2),
+            ..DisplayOpts::default()
+        };
+        assert_eq!(RawValue::Float(2.0 / 3.0).to_display_string_with(&opts), "0.67");
+        assert_eq!(RawValue::Float(-1.0).to_display_string_with(&opts), "-1.00");
+        assert_eq!(RawValue::Integer(7).to_display_string_with(&opts), "7");
+    }
+
+    #[test]
+    fn display_string_uses_timestamp_format_and_bool_labels() {
+        let t = RawValue::Timestamp(
+            DateTime::parse_from_rfc3339("2024-05-01T08:30:05Z").unwrap().to_utc(),
+        );
+        assert_eq!(t.to_display_string(), "2024-05-01T08:30:05+00:00");
+
+        let opts = DisplayOpts {
+            timestamp_format: Some("%d/%m/%Y %H:%M".to_string()),
+            bool_labels: ("on".to_string(), "off".to_string()),
+            ..DisplayOpts::default()
+        };
+        assert_eq!(t.to_display_string_with(&opts), "01/05/2024 08:30");
+        assert_eq!(RawValue::Boolean(true).to_display_string_with(&opts), "on");
+        assert_eq!(RawValue::Boolean(false).to_display_string_with(&opts), "off");
+
+        let rejected = DisplayOpts {
+            timestamp_format: Some("%Q".to_string()),
+            ..DisplayOpts::default()
+        };
+        assert_eq!(t.to_display_string_with(&rejected), "2024-05-01T08:30:05+00:00");
+    }
+
+    #[test]
+    fn protobuf_json_round_trips_each_kind() {
+        let t = DateTime::parse_from_rfc3339("2024-05-01T08:30:00.123456789Z").unwrap().to_utc();
+        let values = [
+            RawValue::String("Front door".into()),
+            RawValue::Integer(i64::MIN),
+            RawValue::Float(-0.25),
+            RawValue::Boolean(true),
+            RawValue::EntityReference("Door1".into()),
+            RawValue::Timestamp(t),
+            RawValue::ConnectionState("Connected".into()),
+            RawValue::GarageDoorState("Closed".into()),
+            RawValue::Blob(vec![1, 2, 3]),
+        ];
+
+        for value in values {
+            let json = value.to_protobuf_json().unwrap();
+            let type_url = json["@type"].as_str().unwrap();
+            assert!(type_url.starts_with(TYPE_URL_PREFIX), "{}", type_url);
+            assert_eq!(RawValue::from_protobuf_json(&json).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn protobuf_json_rejects_what_it_cannot_carry() {
+        assert!(Error::is_unsupported(RawValue::Unspecified.to_protobuf_json().unwrap_err().as_ref()));
+        assert!(RawValue::Float(f64::NAN).to_protobuf_json().is_err());
+
+        let unknown = serde_json::json!({ "@type": "type.googleapis.com/qdb.Color", "raw": "red" });
+        assert!(Error::is_unsupported(RawValue::from_protobuf_json(&unknown).unwrap_err().as_ref()));
+
+        let mistyped = serde_json::json!({ "@type": "type.googleapis.com/qdb.Int", "raw": true });
+        assert!(RawValue::from_protobuf_json(&mistyped).is_err());
+    }
+
+    #[test]
+    fn blobs_round_trip_when_empty_or_not_utf8() {
+        for bytes in [vec![], vec![0xff, 0xfe, 0x00, 0x80, 0xc3]] {
+            let value = RawValue::Blob(bytes.clone());
+
+            let json = value.to_protobuf_json().unwrap();
+            assert_eq!(RawValue::from_protobuf_json(&json).unwrap(), value);
+
+            let text = value.to_display_string();
+            assert_eq!(RawValue::parse(ValueKind::Blob, &text).unwrap(), value);
+        }
+
+        let omitted = serde_json::json!({ "@type": "type.googleapis.com/qdb.Blob" });
+        assert_eq!(RawValue::from_protobuf_json(&omitted).unwrap(), RawValue::Blob(vec![]));
+    }
+
+    #[test]
+    fn with_blob_borrows_the_bytes() {
+        let value = RawValue::Blob(vec![0xff, 0x00]).into_value();
+        assert_eq!(value.with_blob(|b| b.to_vec()).unwrap(), vec![0xff, 0x00]);
+        assert_eq!(value.with_blob(<[u8]>::len).unwrap(), 2);
+        assert!(RawValue::String("ab".into()).into_value().with_blob(<[u8]>::len).is_err());
+    }
+}